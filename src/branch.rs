@@ -15,7 +15,8 @@ use std::borrow::Cow;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 
-pub struct PrevRefErr(RefErr);
+#[derive(Debug)]
+pub struct PrevRefErr(pub RefErr);
 
 impl Display for PrevRefErr {
     fn fmt(&self, formatter: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
@@ -427,6 +428,90 @@ pub fn unlink_branch(repo: &Repository, branch: &LocalBranchName) -> Result<(),
     Ok(())
 }
 
+/**
+ * Make the pipeline links of a branch that has been renamed use its new name.  The old name's
+ * links are removed, and the next and previous branches are linked to the new name.
+ */
+pub fn rename_links<'repo>(
+    repo: &'repo Repository,
+    old: &LocalBranchName,
+    new: &LocalBranchName,
+) -> Result<(), LinkFailure<'repo>> {
+    let next = unlink_siblings(repo, PipeNext::from(old.clone()));
+    let prev = unlink_siblings(repo, PipePrev::from(old.clone()));
+    if let Some(next) = next {
+        check_link_branches(repo, new.clone().into(), next.into())?.link(repo)?;
+    }
+    if let Some(prev) = prev {
+        check_link_branches(repo, prev.into(), new.clone().into())?.link(repo)?;
+    }
+    Ok(())
+}
+
+/**
+ * Check that every pipeline link refers to an existing branch and is matched by a link in the
+ * other direction.  Returns a description of each problem found.
+ */
+pub fn check_links(repo: &Repository) -> Result<Vec<String>, git2::Error> {
+    let mut problems = vec![];
+    for (prefix, inverse_prefix, kind) in [
+        ("refs/pipe-next/", "refs/pipe-prev/", "next"),
+        ("refs/pipe-prev/", "refs/pipe-next/", "previous"),
+    ] {
+        for reference in repo.references_glob(&format!("{}*", prefix))? {
+            let reference = reference?;
+            let Some(name) = reference.name() else {
+                continue;
+            };
+            let branch = &name[prefix.len()..];
+            let Some(target) = reference
+                .symbolic_target()
+                .and_then(|t| t.strip_prefix("refs/heads/"))
+            else {
+                problems.push(format!("{} does not refer to a branch.", name));
+                continue;
+            };
+            if repo
+                .find_reference(&format!("refs/heads/{}", target))
+                .is_err()
+            {
+                problems.push(format!(
+                    "The {} branch of {}, {}, does not exist.",
+                    kind, branch, target
+                ));
+            }
+            let back_target = repo
+                .find_reference(&format!("{}{}", inverse_prefix, target))
+                .ok()
+                .and_then(|r| r.symbolic_target().map(|t| t.to_owned()));
+            if back_target != Some(format!("refs/heads/{}", branch)) {
+                problems.push(format!(
+                    "{} is the {} branch of {}, but is not linked back to it.",
+                    target, kind, branch
+                ));
+            }
+        }
+    }
+    Ok(problems)
+}
+
+/// Find the first branch of every pipeline, i.e. every branch that has a next branch but no
+/// previous branch.
+pub fn find_pipeline_heads(repo: &Repository) -> Result<Vec<LocalBranchName>, git2::Error> {
+    let mut heads = vec![];
+    for reference in repo.references_glob("refs/pipe-next/*")? {
+        let reference = reference?;
+        let Some(name) = reference.name() else {
+            continue;
+        };
+        let branch = LocalBranchName::from(name["refs/pipe-next/".len()..].to_string());
+        if PipePrev::from(branch.clone()).find_reference(repo).is_err() {
+            heads.push(branch);
+        }
+    }
+    Ok(heads)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -6,22 +6,29 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 use super::branch::{
-    check_link_branches, find_target_branchname, resolve_symbolic_reference, unlink_branch,
-    BranchValidationError, NextRefErr, PipeNext, PipePrev, SiblingBranch,
+    check_link_branches, check_links, find_pipeline_heads, find_target_branchname, rename_links,
+    resolve_symbolic_reference, unlink_branch, BranchValidationError, NextRefErr, PipeNext,
+    PipePrev, PrevRefErr, SiblingBranch,
 };
 use super::git::{
-    get_current_branch, get_git_path, get_toplevel, make_git_command, output_to_string,
-    run_git_command, setting_exists, BranchName, BranchyName, GitError, LocalBranchName,
-    OpenRepoError, RefErr, ReferenceSpec, SettingTarget,
+    create_stash, delete_ref, detail, eval_rev_spec, find_operation_in_progress, get_bool_setting,
+    get_current_branch, get_git_path, get_setting, get_toplevel, inform, list_ignored_tracked,
+    list_local_branches, list_recent_branches, make_git_command, output_to_string, run_config,
+    run_git_command, set_head, set_setting, set_verbosity, setting_exists, upsert_ref, verbosity,
+    BranchName, BranchyName, ConfigErr, GitError, LocalBranchName, OpenRepoError, RefErr,
+    ReferenceSpec, SettingLocation, SettingTarget, Verbosity,
 };
 use super::worktree::{
-    append_lines, base_tree, calc_revno, relative_path, set_target, stash_switch, Commit,
-    CommitErr, CommitSpec, Commitish, ExtantRefName, GitStatus, SomethingSpec, SwitchErr,
-    SwitchType, Tree, Treeish, WorktreeHead,
+    append_lines, base_tree, calc_revno, commit_from_revno, determine_switch_target, list_worktree,
+    relative_path, set_target, stash_switch, target_branch_setting, try_apply_wip_stash,
+    BranchCommit, BranchOrCommit, Commit, CommitErr, CommitSpec, Commitish, EntryLocationStatus,
+    EntryState, ExtantRefName, GitStatus, SomethingSpec, StatusEntry, SwitchErr, SwitchOptions,
+    SwitchType, Tree, Treeish, UpstreamInfo, WipReference, WorktreeHead, WorktreeState, EMPTY_TREE,
 };
-use clap::{ArgGroup, Args, Parser, Subcommand};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
 use enum_dispatch::enum_dispatch;
 use git2::Repository;
+use serde::Serialize;
 use std::env;
 use std::ffi::OsString;
 use std::fmt;
@@ -30,7 +37,7 @@ use std::fs;
 use std::io;
 use std::os::unix::process::CommandExt;
 use std::path::{Path, PathBuf};
-use std::process::exit;
+use std::process::{exit, Command};
 use std::str::FromStr;
 
 fn to_strings(cmd_args: &[&str]) -> Vec<String> {
@@ -45,6 +52,23 @@ pub enum MakeArgsErr {
     MergeDiffOpenRepo(OpenRepoError),
     MergeDiffNoRemembered,
     Restore(CommitErr),
+    LogNoHead,
+    LogOpenRepo(OpenRepoError),
+    LogNoCurrentBranch(String),
+    LogPipelinePrev(PrevRefErr),
+    LogPipelineTarget(FindTargetErr),
+    LogStatus(GitError),
+    LogNoUpstream,
+    RewordNoHead,
+    RewordNotHead(String),
+    ExportNoHead,
+    ExportFindTarget(FindTargetErr),
+    RebaseFindTarget(FindTargetErr),
+    CherryStatus(GitError),
+    CherryFindTarget(FindTargetErr),
+    CommitMessageAndFile,
+    RestoreStatus(GitError),
+    RestoreNotUnmerged(Vec<String>),
 }
 
 impl fmt::Display for MakeArgsErr {
@@ -71,17 +95,79 @@ impl fmt::Display for MakeArgsErr {
                 }
                 CommitErr::GitError(err) => err.fmt(f),
             },
+            LogNoHead => write!(f, "Cannot log: no commits in HEAD."),
+            LogOpenRepo(err) => err.fmt(f),
+            LogNoCurrentBranch(err) => write!(f, "{}", err),
+            LogPipelinePrev(err) => err.fmt(f),
+            LogPipelineTarget(err) => match err {
+                FindTargetErr::NoCurrentBranch => write!(f, "No current branch."),
+                FindTargetErr::CommitErr(err) => err.fmt(f),
+                FindTargetErr::NoRemembered => {
+                    write!(
+                        f,
+                        "No previous branch in pipeline and no remembered target."
+                    )
+                }
+            },
+            LogStatus(err) => err.fmt(f),
+            LogNoUpstream => write!(
+                f,
+                "Cannot show unpushed commits: no upstream is configured."
+            ),
+            RewordNoHead => write!(f, "Cannot reword: no commits in HEAD."),
+            RewordNotHead(spec) => write!(
+                f,
+                "{} is not HEAD.  Use \"git rebase -i\" to reword older commits.",
+                spec
+            ),
+            ExportNoHead => write!(f, "Cannot export: no commits in HEAD."),
+            CommitMessageAndFile => write!(f, "Cannot use both --message and --file."),
+            ExportFindTarget(err) => match err {
+                FindTargetErr::NoCurrentBranch => write!(f, "No current branch."),
+                FindTargetErr::CommitErr(err) => err.fmt(f),
+                FindTargetErr::NoRemembered => {
+                    write!(f, "Target not supplied and no remembered target.")
+                }
+            },
+            RebaseFindTarget(err) => match err {
+                FindTargetErr::NoCurrentBranch => write!(f, "No current branch."),
+                FindTargetErr::CommitErr(err) => err.fmt(f),
+                FindTargetErr::NoRemembered => {
+                    write!(f, "Target not supplied and no remembered target.")
+                }
+            },
+            CherryStatus(err) => err.fmt(f),
+            RestoreStatus(err) => err.fmt(f),
+            RestoreNotUnmerged(paths) => write!(
+                f,
+                "These files have no conflicts, so there is no \"ours\" or \"theirs\" version to \
+                restore: {}",
+                paths.join(", ")
+            ),
+            CherryFindTarget(err) => match err {
+                FindTargetErr::NoCurrentBranch => write!(f, "No current branch."),
+                FindTargetErr::CommitErr(err) => err.fmt(f),
+                FindTargetErr::NoRemembered => write!(
+                    f,
+                    "Upstream not supplied, and no upstream configured or remembered target."
+                ),
+            },
             _ => write!(f, ""),
         }
     }
 }
 
 #[derive(Debug, Args)]
-/// Output the contents of a file for a given tree.
+/// Output the contents of a file for a given tree
+///
+/// The contents are emitted raw, without any conversion.
 pub struct Cat {
     #[arg(long, short, default_value = "")]
     tree: String,
     input: String,
+    /// Write the contents to this file instead of stdout.
+    #[arg(long, short, value_name = "FILE")]
+    output: Option<PathBuf>,
 }
 
 enum TreeFile<'a> {
@@ -122,10 +208,50 @@ impl ArgMaker for Cat {
     }
 }
 
+impl Runnable for Cat {
+    fn run(mut self) -> i32 {
+        let output = self.output.take();
+        let args = match self.make_args() {
+            Ok(args) => args,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let Some(output) = output else {
+            args.run_exit()
+        };
+        let file = match fs::File::create(&output) {
+            Ok(file) => file,
+            Err(err) => {
+                eprintln!("Cannot write {}: {}", output.display(), err);
+                return 1;
+            }
+        };
+        let status = match make_git_command(&args).stdout(file).status() {
+            Ok(status) => status,
+            Err(err) => {
+                eprintln!("Could not run git: {}", err);
+                return 1;
+            }
+        };
+        status.code().unwrap_or(1)
+    }
+}
+
 #[derive(Debug, Args)]
 /// Summarize a commit or other object
 pub struct Show {
     commit: Option<CommitSpec>,
+    /// Show only the commit's changes to this file.  (The commit must be supplied.)
+    path: Option<String>,
+    /// Show the contents of the file at the commit, instead of its changes.  (See also "cat".)
+    #[arg(long, requires = "path", conflicts_with_all = ["name_only", "no_log"])]
+    blob: bool,
+    /// For a merge commit, show only the hunks that differ from every parent, i.e. the conflict
+    /// resolutions, instead of the changes from the first parent.
+    #[arg(long, conflicts_with = "blob")]
+    cc: bool,
     /// Emit modified filenames only, not diffs.
     #[arg(long)]
     name_only: bool,
@@ -135,7 +261,17 @@ pub struct Show {
 
 impl ArgMaker for Show {
     fn make_args(self) -> Result<Vec<String>, MakeArgsErr> {
-        let mut cmd = vec!["show", "-m", "--first-parent"];
+        if let (true, Some(path)) = (self.blob, &self.path) {
+            let commit = self.commit.as_ref().map_or("HEAD", |c| c.spec.as_str());
+            let tree_file = TreeFile::CommitFile { commit, path };
+            return Ok(to_strings(&["show", &format_tree_file(&tree_file)]));
+        }
+        let mut cmd = vec!["show"];
+        if self.cc {
+            cmd.push("--cc");
+        } else {
+            cmd.extend(["-m", "--first-parent"]);
+        }
         if self.name_only {
             cmd.push("--name-only");
         }
@@ -144,12 +280,23 @@ impl ArgMaker for Show {
         }
         let mut cmd = to_strings(&cmd);
         cmd.extend(self.commit.into_iter().map(|c| c.spec));
+        if let Some(path) = self.path {
+            cmd.push("--".to_string());
+            cmd.push(path);
+        }
         Ok(cmd)
     }
 }
 
 #[derive(Debug, Args)]
-/// Compare one tree to another.
+#[clap(group(ArgGroup::new("summary").args(&["name_only", "name_status", "stat"])))]
+/**
+Compare one tree to another.
+
+By default, the working tree is compared to HEAD, so both staged and unstaged changes are shown.
+Use --staged to show only staged changes (the index compared to HEAD), or --unstaged to show only
+unstaged changes (the working tree compared to the index).
+*/
 pub struct Diff {
     /// Source commit / branch to compare.  (Defaults to HEAD.)
     #[arg(long, short)]
@@ -163,6 +310,32 @@ pub struct Diff {
     /// Emit modified filenames only, not diffs.
     #[arg(long)]
     name_only: bool,
+    /// Emit modified filenames with the kind of change (e.g. "M" for modified), not diffs.
+    #[arg(long)]
+    name_status: bool,
+    /// Emit the number of lines inserted and deleted in each file, not diffs.
+    #[arg(long)]
+    stat: bool,
+    /// Report whitespace errors instead of showing a diff.  Exits non-zero if there are any.
+    #[arg(long)]
+    check: bool,
+    /// Write the diff to this file instead of stdout.  Use "-" for stdout.
+    #[arg(long, value_name = "FILE")]
+    output: Option<String>,
+    /// Combine hunks that are separated by up to N lines.
+    #[arg(long, value_name = "N")]
+    inter_hunk_context: Option<u32>,
+    /// Show a combined diff of the target merge commit against all its parents, showing only the
+    /// conflict resolutions.  Without a target, show the unresolved conflicts of a merge in
+    /// progress.
+    #[arg(long, conflicts_with = "source")]
+    cc: bool,
+    /// Show only staged changes, comparing the index to the source.
+    #[arg(long, conflicts_with_all = ["target", "cc"])]
+    staged: bool,
+    /// Show only unstaged changes, comparing the working tree to the index.
+    #[arg(long, conflicts_with_all = ["source", "target", "cc", "staged"])]
+    unstaged: bool,
     /// Files to compare.  If empty, all are compared.
     path: Vec<String>,
 }
@@ -176,17 +349,52 @@ impl ArgMaker for Diff {
         if self.name_only {
             cmd_args.push("--name-only");
         }
+        if self.name_status {
+            cmd_args.push("--name-status");
+        }
+        if self.stat {
+            cmd_args.push("--stat");
+        }
+        if self.check {
+            cmd_args.push("--check");
+        }
+        if self.cc {
+            cmd_args.push("--cc");
+        }
+        if self.staged {
+            cmd_args.push("--cached");
+        }
         let mut cmd_args = to_strings(&cmd_args);
-        cmd_args.push(match &self.source {
-            Some(source) => source.sha.to_owned(),
-            None => match base_tree().map(|x| x.get_tree_reference().into()) {
-                Ok(tree) => tree,
-                Err(err) => {
-                    return Err(MakeArgsErr::GetTreeRefFailure(err));
-                }
-            },
-        });
-        cmd_args.extend(self.target.into_iter().map(|t| t.sha));
+        if let Some(output) = self.output.filter(|o| o != "-") {
+            cmd_args.push(format!("--output={}", output));
+        }
+        if let Some(lines) = self.inter_hunk_context {
+            cmd_args.push(format!("--inter-hunk-context={}", lines));
+        }
+        if self.cc {
+            // The merge commit is compared to all its parents.
+            if let Some(target) = self.target {
+                cmd_args.push(target.sha.clone());
+                cmd_args.push(format!("{}^@", target.sha));
+            }
+        } else if !self.unstaged {
+            // With --staged, "--cached" compares the index to the source.
+            cmd_args.push(match &self.source {
+                Some(source) => source.sha.to_owned(),
+                None => match base_tree().map(|x| x.get_tree_reference().into()) {
+                    Ok(tree) => {
+                        if tree == EMPTY_TREE {
+                            inform("No commits yet; showing all tracked content as additions.");
+                        }
+                        tree
+                    }
+                    Err(err) => {
+                        return Err(MakeArgsErr::GetTreeRefFailure(err));
+                    }
+                },
+            });
+            cmd_args.extend(self.target.into_iter().map(|t| t.sha));
+        }
         if !self.path.is_empty() {
             cmd_args.push("--".to_string());
             cmd_args.extend(self.path);
@@ -201,25 +409,131 @@ pub struct Log {
     /// The range of commits to display.  Defaults to all of HEAD.
     #[arg(long, short)]
     range: Option<String>,
+    /// Show only commits since this branch diverged from the previous branch in its pipeline (or
+    /// from its remembered target, for the first branch).
+    #[arg(long, conflicts_with = "range")]
+    pipeline: bool,
+    /// Show only commits that are not on the upstream branch, i.e. the commits a push would
+    /// send.
+    #[arg(long, conflicts_with_all = ["range", "pipeline"])]
+    unpushed: bool,
+    /// Show commits from all branches and tags, not just HEAD.  Implies --include-merged.
+    #[arg(long, conflicts_with_all = ["range", "pipeline", "unpushed"])]
+    all: bool,
     /// If enabled, show patches for commits.
     #[arg(long, short)]
     patch: bool,
+    /// Show a summary of the changed files before each patch.
+    #[arg(long, conflicts_with = "patch")]
+    patch_with_stat: bool,
+    /// Show the files each commit creates, deletes or renames, and any mode changes.
+    #[arg(long)]
+    summary: bool,
+    /// Show each commit on a single line.
+    #[arg(long, short, conflicts_with_all = ["patch", "patch_with_stat"])]
+    oneline: bool,
+    /// Show the oldest commits first, i.e. in the order they were applied.  With --limit, the
+    /// newest N commits are still the ones shown.
+    #[arg(long)]
+    reverse: bool,
     /// If enabled, show merged commits.  (Merge commits are always shown.)
     #[arg(long, short)]
     include_merged: bool,
+    /// Show at most N commits.  Without --include-merged, only first-parent commits are counted.
+    #[arg(long, short = 'n', value_name = "N")]
+    limit: Option<u32>,
+    /// Show only commits whose author matches this pattern.
+    #[arg(long, value_name = "PATTERN")]
+    author: Option<String>,
+    /// Show only commits whose committer matches this pattern.  Combined with --author, both must
+    /// match.
+    #[arg(long, value_name = "PATTERN")]
+    committer: Option<String>,
     /// Show only commits in which these files were modified.  (No filter if none supplied.)
     path: Vec<String>,
 }
 
+/**
+ * Find the commit that the current branch's pipeline entry is based on.
+ *
+ * This is the merge base with the previous branch in the pipeline, or with the remembered target
+ * if there is no previous branch.
+ */
+fn find_pipeline_base(head: &Commit) -> Result<Commit, MakeArgsErr> {
+    let repo = Repository::open_from_env()
+        .map_err(OpenRepoError::from)
+        .map_err(MakeArgsErr::LogOpenRepo)?;
+    let current = get_local_current(&repo).map_err(MakeArgsErr::LogNoCurrentBranch)?;
+    let base = match advance::<PipePrev>(&repo, current)
+        .map_err(|e| MakeArgsErr::LogPipelinePrev(e.into()))?
+    {
+        Some(prev) => {
+            Commit::from_str(&prev.full()).map_err(|e| MakeArgsErr::LogPipelineTarget(e.into()))?
+        }
+        None => CommitSpec::from(find_target().map_err(MakeArgsErr::LogPipelineTarget)?)
+            .as_ref()
+            .clone(),
+    };
+    Ok(head.find_merge_base(&base))
+}
+
 impl ArgMaker for Log {
     fn make_args(self) -> Result<Vec<String>, MakeArgsErr> {
         let mut cmd_args = vec!["log"];
-        if !self.include_merged {
+        // First-parent history is not meaningful across multiple tips.
+        if self.all {
+            cmd_args.push("--all");
+        } else if !self.include_merged {
             cmd_args.push("--first-parent");
         }
         if self.patch {
             cmd_args.extend(["-m", "--patch"]);
         }
+        if self.patch_with_stat {
+            cmd_args.extend(["-m", "--patch", "--stat"]);
+        }
+        if self.summary {
+            cmd_args.push("--summary");
+        }
+        if self.oneline {
+            cmd_args.push("--oneline");
+        }
+        if self.reverse {
+            cmd_args.push("--reverse");
+        }
+        let limit_arg;
+        if let Some(limit) = self.limit {
+            limit_arg = format!("-n{}", limit);
+            cmd_args.push(&limit_arg);
+        }
+        let author_arg;
+        if let Some(author) = &self.author {
+            author_arg = format!("--author={}", author);
+            cmd_args.push(&author_arg);
+        }
+        let committer_arg;
+        if let Some(committer) = &self.committer {
+            committer_arg = format!("--committer={}", committer);
+            cmd_args.push(&committer_arg);
+        }
+        let pipeline_range;
+        if self.pipeline {
+            let head = Commit::from_str("HEAD").map_err(|_| MakeArgsErr::LogNoHead)?;
+            pipeline_range = format!("{}..{}", find_pipeline_base(&head)?.sha, head.sha);
+            cmd_args.push(&pipeline_range);
+        }
+        let unpushed_range;
+        if self.unpushed {
+            let upstream = match GitStatus::new().map_err(MakeArgsErr::LogStatus)?.head {
+                WorktreeHead::Attached {
+                    upstream: Some(upstream),
+                    ..
+                } => upstream.name,
+                _ => return Err(MakeArgsErr::LogNoUpstream),
+            };
+            unpushed_range = format!("{}..HEAD", upstream);
+            cmd_args.push(&unpushed_range);
+        }
         cmd_args.extend(self.range.iter().map(|s| s.as_str()));
         let mut cmd_args = to_strings(&cmd_args);
         if !self.path.is_empty() {
@@ -256,6 +570,18 @@ fn find_target() -> Result<ExtantRefName, FindTargetErr> {
     ExtantRefName::try_from(result).map_err(|e| e.into())
 }
 
+/// Report the use of a remembered branch.  Its full name is given when verbose.
+fn report_remembered(repo: &Repository, spec: &impl ReferenceSpec) {
+    if verbosity() >= Verbosity::Verbose {
+        inform(format!("Using remembered value {:?}", spec.full()));
+    } else {
+        inform(format!(
+            "Using remembered value {:?}",
+            spec.find_shortest(repo)
+        ));
+    }
+}
+
 /// Ensure a source branch is set, falling back to remembered branch.
 fn ensure_source(repo: &Repository, source: Option<CommitSpec>) -> Result<CommitSpec, i32> {
     if let Some(source) = source {
@@ -264,7 +590,7 @@ fn ensure_source(repo: &Repository, source: Option<CommitSpec>) -> Result<Commit
     use FindTargetErr::*;
     match find_target() {
         Ok(spec) => {
-            eprintln!("Using remembered value {:?}", spec.find_shortest(repo));
+            report_remembered(repo, &spec);
             Ok(spec.into())
         }
         Err(NoCurrentBranch) => {
@@ -294,10 +620,115 @@ pub struct Merge {
     /// Commit as part of the merge.
     #[arg(long)]
     commit: bool,
+    /// Switch to this branch and merge the current branch into it.  The working tree must be
+    /// clean.  On conflict, you are left on this branch to resolve them.
+    #[arg(long, value_name = "BRANCH", conflicts_with_all = ["source", "remember"])]
+    into: Option<String>,
+    /// Include the subjects of (at most N) merged commits in the merge commit message.
+    #[arg(long, value_name = "N", num_args = 0..=1, require_equals = true)]
+    log: Option<Option<u32>>,
+    /// Pass an option to the merge strategy, e.g. "ours", "theirs" or "rename-threshold=50".
+    /// May be repeated.
+    #[arg(long, short = 'X', value_name = "OPTION")]
+    strategy_option: Vec<String>,
+    /// Apply the changes without recording a merge, so the next commit has a single parent.
+    /// Useful when backporting only some of the changes, since recording the merge would stop
+    /// the rest from being merged later.
+    #[arg(long, conflicts_with_all = ["remember", "commit"])]
+    no_record: bool,
+    /// Abandon a conflicted merge, restoring the tree to its state before the merge.  A
+    /// remembered source is left alone, since it is only remembered when a merge succeeds.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "source",
+            "remember",
+            "commit",
+            "into",
+            "log",
+            "strategy_option",
+            "no_record"
+        ]
+    )]
+    abort: bool,
+}
+
+/// Switch to the target branch and merge the previously-current branch into it.
+fn merge_into(target: String, merge: Merge) -> i32 {
+    let status = match GitStatus::new() {
+        Ok(status) => status,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 1;
+        }
+    };
+    let WorktreeHead::Attached { head: source, .. } = &status.head else {
+        eprintln!("Cannot merge into {}: no current branch.", target);
+        return 1;
+    };
+    if status
+        .iter()
+        .any(|se| !matches!(se.state, EntryState::Untracked | EntryState::Ignored))
+    {
+        eprintln!(
+            "Cannot merge into {}: the working tree has uncommitted changes.",
+            target
+        );
+        return 1;
+    }
+    // The tree is clean, so there is nothing to stash, and the target's WIP is left alone so that
+    // it cannot interfere with the merge.
+    let code = handle_switch(SwitchType::PlainSwitch(BranchyName::UnresolvedName(
+        target.clone(),
+    )));
+    if code != 0 {
+        return code;
+    }
+    let commit = merge.commit;
+    let merge = Merge {
+        source: CommitSpec::from_str(source.branch_name()).ok(),
+        into: None,
+        ..merge
+    };
+    let code = merge.run();
+    if code != 0 {
+        eprintln!(
+            "Merging {} into {} stopped.  Resolve any conflicts on {}, then commit.",
+            source.branch_name(),
+            target,
+            target
+        );
+    } else if commit {
+        inform(format!("Merged {} into {}.", source.branch_name(), target));
+    } else {
+        inform(format!(
+            "Merged {} into {}.  Use \"oaf commit\" to complete the merge.",
+            source.branch_name(),
+            target
+        ));
+    }
+    code
 }
 
 impl Runnable for Merge {
     fn run(self) -> i32 {
+        if self.abort {
+            if !get_git_path("MERGE_HEAD").exists() {
+                eprintln!("No merge to abort.");
+                return 1;
+            }
+            let status = match make_git_command(&["merge", "--abort"]).status() {
+                Ok(status) => status,
+                Err(err) => {
+                    eprintln!("Could not run git: {}", err);
+                    return 1;
+                }
+            };
+            return status.code().unwrap_or(1);
+        }
+        if let Some(into) = self.into.clone() {
+            return merge_into(into, self);
+        }
         let current_branch = get_current_branch().expect("Current branch");
         let repo = match Repository::open_from_env().map_err(OpenRepoError::from) {
             Ok(repo) => repo,
@@ -313,10 +744,34 @@ impl Runnable for Merge {
         if !self.commit {
             args.push("--no-commit");
         }
+        let log_arg;
+        if let Some(log) = self.log {
+            log_arg = match log {
+                Some(count) => format!("--log={}", count),
+                None => "--log".to_string(),
+            };
+            args.push(&log_arg);
+        }
+        let strategy_args: Vec<String> = self
+            .strategy_option
+            .iter()
+            .map(|option| format!("-X{}", option))
+            .collect();
+        args.extend(strategy_args.iter().map(|s| s.as_str()));
         args.push(&source.spec);
         let mut cmd = make_git_command(&args);
         let Ok(status) = cmd.status() else { return 1 };
         let Some(code) = status.code() else { return 1 };
+        if self.no_record {
+            if code != 0 {
+                eprintln!("Warning: the merge had conflicts, so it is still recorded.");
+                return code;
+            }
+            if let Err(err) = run_git_command(&["update-ref", "-d", "MERGE_HEAD"]) {
+                eprint!("{}", String::from_utf8_lossy(&err.stderr));
+                return 1;
+            }
+        }
         if code != 0 || !self.remember {
             return code;
         };
@@ -349,6 +804,7 @@ Display a diff predicting the changes that would be merged if you merged your wo
 The diff includes uncommitted changes, unlike `git diff <target>...`.  It is produced by
 diffing the working tree against the merge base of <target> and HEAD.
 */
+#[clap(group(ArgGroup::new("summary").args(&["name_only", "stat"])))]
 pub struct MergeDiff {
     /// The branch you would merge into.  (Though any commitish will work.)
     #[arg(long, short)]
@@ -359,18 +815,22 @@ pub struct MergeDiff {
     /// Emit modified filenames only, not diffs.
     #[arg(long)]
     name_only: bool,
+    /// Emit the number of lines inserted and deleted in each file, not diffs.
+    #[arg(long)]
+    stat: bool,
     path: Vec<String>,
     #[arg(long)]
     remember: bool,
 }
 
 impl MergeDiff {
-    fn make_args(self) -> Result<Vec<String>, MakeArgsErr> {
+    /// Determine the target, falling back to the remembered target.
+    fn resolve_target(&mut self) -> Result<CommitSpec, MakeArgsErr> {
         if Commit::from_str("HEAD").is_err() {
             return Err(MakeArgsErr::MergeDiffNoHead);
         }
         use FindTargetErr::*;
-        let target = match self.target {
+        Ok(match self.target.take() {
             Some(target) => target,
             None => match find_target() {
                 Ok(spec) => {
@@ -380,19 +840,30 @@ impl MergeDiff {
                             return Err(MakeArgsErr::MergeDiffOpenRepo(err));
                         }
                     };
-                    eprintln!("Using remembered value {:?}", spec.find_shortest(&repo));
+                    report_remembered(&repo, &spec);
                     Ok(spec.into())
                 }
                 Err(NoCurrentBranch) => Err(MakeArgsErr::MergeDiffFindTarget(NoCurrentBranch)),
                 Err(CommitErr(err)) => Err(MakeArgsErr::MergeDiffFindTarget(CommitErr(err))),
                 Err(NoRemembered) => Err(MakeArgsErr::MergeDiffFindTarget(NoRemembered)),
             }?,
-        };
+        })
+    }
+
+    fn make_args(self, target: CommitSpec) -> Result<Vec<String>, MakeArgsErr> {
         Diff {
             source: Some(target.find_merge_base(CommitSpec::from_str("HEAD").unwrap().as_ref())),
             target: None,
             myers: self.myers,
             name_only: self.name_only,
+            name_status: false,
+            stat: self.stat,
+            check: false,
+            output: None,
+            inter_hunk_context: None,
+            cc: false,
+            staged: false,
+            unstaged: false,
             path: self.path,
         }
         .make_args()
@@ -400,16 +871,25 @@ impl MergeDiff {
 }
 
 impl Runnable for MergeDiff {
-    fn run(self) -> i32 {
-        if self.remember {
+    fn run(mut self) -> i32 {
+        // A remembered target does not need to be remembered again.
+        let remember = self.remember && self.target.is_some();
+        let target = match self.resolve_target() {
+            Ok(target) => target,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        if remember {
             let current_branch = get_current_branch().expect("Current branch");
-            if let Some(target) = self.target.as_ref().and_then(|t| {
-                ExtantRefName::resolve(&t.get_commit_spec()).and_then(|s| s.name.ok())
-            }) {
+            if let Some(target) =
+                ExtantRefName::resolve(&target.get_commit_spec()).and_then(|s| s.name.ok())
+            {
                 set_target(&current_branch, &target).expect("Could not set target branch.");
             }
         }
-        let args = match self.make_args() {
+        let args = match self.make_args(target) {
             Ok(args) => args,
             Err(err) => {
                 eprintln!("{}", err);
@@ -422,6 +902,190 @@ impl Runnable for MergeDiff {
     }
 }
 
+#[derive(Debug, Args)]
+/**
+Apply the changes from existing commits to the current branch.
+
+Each commit is recorded as a new commit.  Ranges such as "a..b" are accepted.
+*/
+pub struct CherryPick {
+    /// The commits (or ranges of commits) to apply.
+    #[arg(required = true, value_name = "COMMIT")]
+    commits: Vec<String>,
+    /// Apply the changes without committing them.
+    #[arg(long, short)]
+    no_commit: bool,
+}
+
+impl Runnable for CherryPick {
+    fn run(self) -> i32 {
+        // Ranges are passed through to git unparsed.
+        for commit in &self.commits {
+            if !commit.contains("..") && CommitSpec::from_str(commit).is_err() {
+                eprintln!("{} is not a commit.", commit);
+                return 1;
+            }
+        }
+        let mut args = vec!["cherry-pick"];
+        if self.no_commit {
+            args.push("--no-commit");
+        }
+        args.extend(self.commits.iter().map(|c| c.as_str()));
+        let Ok(output) = make_git_command(&args).output() else {
+            return 1;
+        };
+        print!("{}", String::from_utf8_lossy(&output.stdout));
+        if output.status.success() {
+            return 0;
+        }
+        let status = match GitStatus::new() {
+            Ok(status) => status,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let unmerged: Vec<StatusEntry> = status
+            .iter()
+            .filter(|se| matches!(se.state, EntryState::Unmerged { .. }))
+            .collect();
+        // Without conflicts, git's own explanation is the most useful.
+        if unmerged.is_empty() {
+            eprint!("{}", String::from_utf8_lossy(&output.stderr));
+            return output.status.code().unwrap_or(1);
+        }
+        let cwd = env::current_dir().expect("Need cwd");
+        let top = match get_toplevel() {
+            Ok(top) => top,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let top_rel = cwd.strip_prefix(top).unwrap();
+        eprintln!("Cherry-pick stopped with conflicts:");
+        for se in unmerged {
+            eprintln!("  {}", se.format_entry(&top_rel));
+        }
+        if self.no_commit {
+            eprintln!("Resolve the conflicts, then use \"oaf commit\" to commit the changes.");
+        } else if get_git_path("sequencer/todo").exists() {
+            eprintln!(
+                "Resolve the conflicts and use \"oaf commit\", then \"git cherry-pick \
+                --continue\" to apply the remaining commits."
+            );
+        } else {
+            eprintln!(
+                "Resolve the conflicts, then use \"oaf commit\" to complete the cherry-pick."
+            );
+        }
+        1
+    }
+}
+
+#[derive(Debug, Args)]
+#[clap(group(ArgGroup::new("side").args(&["ours", "theirs"]).required(true)))]
+/**
+Resolve conflicts by taking one side's version of each file.
+
+The resolved files are staged, and any remaining conflicts are listed.  (During a rebase, the
+sides are swapped, as they are in git.)
+*/
+pub struct Resolve {
+    /// Take the version from the current branch.
+    #[arg(long)]
+    ours: bool,
+    /// Take the version from the branch being merged.
+    #[arg(long)]
+    theirs: bool,
+    /// Resolve every conflicted file.
+    #[arg(long, conflicts_with = "path")]
+    all: bool,
+    /// The conflicted files to resolve.
+    #[arg(required_unless_present = "all")]
+    path: Vec<String>,
+}
+
+/// Run a git command, passing through its error output on failure.  Returns the exit code.
+fn run_in_dir(args: &[&str], dir: &str) -> i32 {
+    let Ok(output) = make_git_command(args).current_dir(dir).output() else {
+        return 1;
+    };
+    if !output.status.success() {
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+    }
+    output.status.code().unwrap_or(1)
+}
+
+impl Runnable for Resolve {
+    fn run(self) -> i32 {
+        let top = match get_toplevel() {
+            Ok(top) => top,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let cwd = env::current_dir().expect("Need cwd");
+        let unmerged_files = || -> Result<Vec<String>, GitError> {
+            Ok(GitStatus::new()?
+                .iter()
+                .filter(|se| matches!(se.state, EntryState::Unmerged { .. }))
+                .map(|se| se.filename.to_owned())
+                .collect())
+        };
+        // Unmerged files are listed relative to the top of the tree.
+        let (paths, dir) = if self.all {
+            match unmerged_files() {
+                Ok(paths) => (paths, top.clone()),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return 1;
+                }
+            }
+        } else {
+            (self.path, cwd.to_string_lossy().into_owned())
+        };
+        if paths.is_empty() {
+            eprintln!("No conflicts to resolve.");
+            return 1;
+        }
+        let side = if self.ours { "--ours" } else { "--theirs" };
+        let mut checkout_args = vec!["checkout", side, "--"];
+        checkout_args.extend(paths.iter().map(|p| p.as_str()));
+        let code = run_in_dir(&checkout_args, &dir);
+        if code != 0 {
+            return code;
+        }
+        let mut add_args = vec!["add", "--"];
+        add_args.extend(paths.iter().map(|p| p.as_str()));
+        let code = run_in_dir(&add_args, &dir);
+        if code != 0 {
+            return code;
+        }
+        let remaining = match unmerged_files() {
+            Ok(remaining) => remaining,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        if remaining.is_empty() {
+            inform("All conflicts resolved.");
+            return 0;
+        }
+        let top_rel = cwd.strip_prefix(&top).unwrap();
+        println!("Remaining conflicts:");
+        for path in remaining {
+            match relative_path(top_rel, &path) {
+                Ok(path) => println!("  {}", path.to_string_lossy()),
+                Err(_) => println!("  {}", path),
+            }
+        }
+        0
+    }
+}
+
 #[derive(Debug, Args)]
 /// Transfer remote changes to the local repository and working tree
 pub struct Pull {
@@ -446,20 +1110,69 @@ pub struct Restore {
     /// Tree/commit/branch containing the version of the file to restore.
     #[arg(long, short)]
     source: Option<SomethingSpec>,
+    /// Restore only the staged version of the file(s), i.e. unstage changes.  The working tree is
+    /// left untouched.
+    #[arg(long)]
+    staged: bool,
+    /// Restore our version of conflicted file(s), i.e. the version from the current branch.
+    #[arg(long, conflicts_with_all = ["source", "staged", "theirs"])]
+    ours: bool,
+    /// Restore their version of conflicted file(s), i.e. the version being merged in.
+    #[arg(long, conflicts_with_all = ["source", "staged"])]
+    theirs: bool,
     /// File(s) to restore
     #[arg(required = true)]
     path: Vec<String>,
 }
 
+/// Return the paths that are not unmerged in the working tree.
+fn find_merged_paths(paths: &[String]) -> Result<Vec<String>, GitError> {
+    let unmerged = GitStatus::new()?.unmerged_filenames();
+    let top = PathBuf::from(get_toplevel()?)
+        .canonicalize()
+        .map_err(|err| GitError::UnknownError(err.to_string().into()))?;
+    Ok(paths
+        .iter()
+        .filter(|path| {
+            let top_relative = normpath(Path::new(path))
+                .ok()
+                .and_then(|abspath| relative_path(&top, abspath).ok());
+            !top_relative.is_some_and(|top_relative| {
+                unmerged.iter().any(|file| Path::new(file) == top_relative)
+            })
+        })
+        .cloned()
+        .collect())
+}
+
 impl ArgMaker for Restore {
     fn make_args(self) -> Result<Vec<String>, MakeArgsErr> {
+        if self.ours || self.theirs {
+            let merged = find_merged_paths(&self.path).map_err(MakeArgsErr::RestoreStatus)?;
+            if !merged.is_empty() {
+                return Err(MakeArgsErr::RestoreNotUnmerged(merged));
+            }
+            let side = if self.ours { "--ours" } else { "--theirs" };
+            let mut cmd_args = to_strings(&["checkout", side, "--"]);
+            cmd_args.extend(self.path);
+            return Ok(cmd_args);
+        }
         let source = self
             .source
             .ok_or(())
             .or_else(|_| SomethingSpec::from_str("HEAD"))
             .map_err(MakeArgsErr::Restore)?;
 
-        let mut cmd_args = to_strings(&["checkout", &source.get_treeish_spec()]);
+        let mut cmd_args = if self.staged {
+            to_strings(&[
+                "restore",
+                "--staged",
+                "--source",
+                &source.get_treeish_spec(),
+            ])
+        } else {
+            to_strings(&["checkout", &source.get_treeish_spec()])
+        };
         if !self.path.is_empty() {
             cmd_args.push("--".to_string());
             cmd_args.extend(self.path);
@@ -469,38 +1182,387 @@ impl ArgMaker for Restore {
 }
 
 #[derive(Debug, Args)]
-/// Revert a previous commit.
+/// Revert previous commits.
 pub struct Revert {
-    /// The commit to revert
-    source: CommitSpec,
+    /// The commits to revert, in the order to revert them
+    #[arg(required = true, value_name = "COMMIT")]
+    commits: Vec<CommitSpec>,
+    /// Apply the reverse changes without committing them, so that several reverts can be
+    /// committed together.
+    #[arg(long, short)]
+    no_commit: bool,
+    /// For merge commits, the parent to revert against.
+    #[arg(long, short, value_name = "N", default_value_t = 1)]
+    mainline: u32,
 }
 
 impl ArgMaker for Revert {
     fn make_args(self) -> Result<Vec<String>, MakeArgsErr> {
-        let cmd_args = to_strings(&["revert", "-m1", &self.source.get_commit_spec()]);
+        let mut cmd_args = vec!["revert".to_string(), format!("-m{}", self.mainline)];
+        if self.no_commit {
+            cmd_args.push("-n".to_string());
+        }
+        cmd_args.extend(
+            self.commits
+                .iter()
+                .map(|c| c.get_commit_spec().into_owned()),
+        );
         Ok(cmd_args)
     }
 }
 
-#[enum_dispatch]
-#[derive(Debug, Subcommand)]
-pub enum RewriteCommand {
-    Cat,
-    Show,
-    Diff,
-    Log,
-    Pull,
-    PushTags,
-    Restore,
-    Revert,
+#[derive(Debug, Args)]
+/**
+Change the message of the HEAD commit.
+
+The tree and index are left untouched, so staged changes are not added to the commit.
+*/
+pub struct Reword {
+    /// The commit to reword.  Only HEAD is supported.
+    commit: Option<CommitSpec>,
+    /// Use this message instead of opening an editor.
+    #[arg(long, short)]
+    message: Option<String>,
 }
 
-#[enum_dispatch]
-#[derive(Debug, Parser)]
-pub enum NativeCommand {
-    DisconnectBranch,
+impl ArgMaker for Reword {
+    fn make_args(self) -> Result<Vec<String>, MakeArgsErr> {
+        let head = Commit::from_str("HEAD").map_err(|_| MakeArgsErr::RewordNoHead)?;
+        if let Some(commit) = self.commit {
+            if commit.as_ref().sha != head.sha {
+                return Err(MakeArgsErr::RewordNotHead(commit.spec));
+            }
+        }
+        // With no paths, --only commits none of the index, so only the message changes.
+        let mut cmd_args = vec!["commit", "--amend", "--only"];
+        if let Some(message) = &self.message {
+            cmd_args.extend(["--message", message]);
+        }
+        Ok(to_strings(&cmd_args))
+    }
+}
+
+#[derive(Debug, Args)]
+/**
+Export the commits on the current branch as patch files.
+
+The commits exported are those since the merge base of HEAD and the target, which defaults to
+the remembered target.
+*/
+pub struct Export {
+    /// The branch the commits would be merged into.  (Though any commitish will work.)
+    #[arg(long, short)]
+    target: Option<CommitSpec>,
+    /// Write all patches to stdout as a single stream, instead of to files.
+    #[arg(long, conflicts_with = "output_directory")]
+    stdout: bool,
+    /// Write the patch files to this directory, instead of the current directory.
+    #[arg(long, short, value_name = "DIR")]
+    output_directory: Option<String>,
+}
+
+impl ArgMaker for Export {
+    fn make_args(self) -> Result<Vec<String>, MakeArgsErr> {
+        let head = Commit::from_str("HEAD").map_err(|_| MakeArgsErr::ExportNoHead)?;
+        let target = match self.target {
+            Some(target) => target,
+            None => find_target().map_err(MakeArgsErr::ExportFindTarget)?.into(),
+        };
+        let range = format!("{}..{}", target.find_merge_base(&head).sha, head.sha);
+        let mut cmd_args = vec!["format-patch"];
+        if self.stdout {
+            cmd_args.push("--stdout");
+        }
+        if let Some(output_directory) = &self.output_directory {
+            cmd_args.extend(["--output-directory", output_directory]);
+        }
+        cmd_args.push(&range);
+        Ok(to_strings(&cmd_args))
+    }
+}
+
+#[derive(Debug, Args)]
+/**
+List the commits on the current branch, showing which are not yet upstream.
+
+Commits marked "+" are not upstream.  Commits marked "-" have an equivalent change upstream,
+e.g. because they were cherry-picked.
+*/
+pub struct Cherry {
+    /// The branch to compare with.  Defaults to the configured upstream, or the remembered
+    /// target if there is no upstream.
+    upstream: Option<CommitSpec>,
+}
+
+impl ArgMaker for Cherry {
+    fn make_args(self) -> Result<Vec<String>, MakeArgsErr> {
+        let upstream = match self.upstream {
+            Some(upstream) => upstream.spec,
+            None => match GitStatus::new().map_err(MakeArgsErr::CherryStatus)?.head {
+                WorktreeHead::Attached {
+                    upstream: Some(upstream),
+                    ..
+                } => upstream.name,
+                _ => find_target()
+                    .map_err(MakeArgsErr::CherryFindTarget)?
+                    .full()
+                    .into_owned(),
+            },
+        };
+        Ok(to_strings(&["cherry", "-v", &upstream]))
+    }
+}
+
+#[derive(Debug, Args)]
+/// Reapply the commits on the current branch on top of another branch.
+pub struct Rebase {
+    /// The branch to rebase onto.  Defaults to the remembered target.
+    target: Option<CommitSpec>,
+    /// Edit the list of commits to rebase before rebasing.
+    #[arg(long, short)]
+    interactive: bool,
+    /// Move "fixup!" and "squash!" commits next to the commits they modify, and squash them.
+    /// Without --interactive, this happens without opening an editor.
+    #[arg(long)]
+    autosquash: bool,
+    /// After rebasing, check that the pipeline links are still consistent, as "pipeline --check"
+    /// does.
+    #[arg(long)]
+    keep_pipeline: bool,
+    /// Abort the rebase in progress, and restore any uncommitted changes that were stashed when
+    /// it started.
+    #[arg(
+        long,
+        conflicts_with_all = ["target", "interactive", "autosquash", "keep_pipeline"]
+    )]
+    abort: bool,
+}
+
+/// The reference that holds the uncommitted changes stashed when a rebase of the branch started.
+fn rebase_wip_ref(branch: &LocalBranchName) -> String {
+    format!("refs/oaf-rebase-wip/{}", branch.branch_name())
+}
+
+/// Stash any uncommitted changes to tracked files, so that the rebase can start, and return the
+/// reference holding them.
+fn stash_rebase_wip() -> Result<Option<String>, String> {
+    let Ok(branch) = get_current_branch() else {
+        return Ok(None);
+    };
+    let Some(oid) = create_stash(false) else {
+        return Ok(None);
+    };
+    let wip_ref = rebase_wip_ref(&branch);
+    upsert_ref(&wip_ref, &oid)
+        .map_err(|_| format!("Could not set reference {} to {}", wip_ref, oid))?;
+    run_git_command(&["reset", "--hard", "--quiet"])
+        .map_err(|err| String::from_utf8_lossy(&err.stderr).trim_end().to_string())?;
+    Ok(Some(wip_ref))
+}
+
+/// Apply the uncommitted changes stashed in the reference, and delete it.
+fn restore_rebase_wip(wip_ref: &str) -> Result<(), String> {
+    run_git_command(&["stash", "apply", "--quiet", wip_ref])
+        .map_err(|err| String::from_utf8_lossy(&err.stderr).trim_end().to_string())?;
+    delete_ref(wip_ref).map_err(|_| format!("Could not delete {}", wip_ref))
+}
+
+impl Rebase {
+    fn abort() -> i32 {
+        let mut result = 0;
+        if let Err(err) = run_git_command(&["rebase", "--abort"]) {
+            eprint!("{}", String::from_utf8_lossy(&err.stderr));
+            result = 1;
+        }
+        if find_operation_in_progress().is_some() {
+            return 1;
+        }
+        let Ok(branch) = get_current_branch() else {
+            return result;
+        };
+        let wip_ref = rebase_wip_ref(&branch);
+        if eval_rev_spec(&wip_ref).is_err() {
+            return result;
+        }
+        if let Err(err) = restore_rebase_wip(&wip_ref) {
+            eprintln!("{}", err);
+            return 1;
+        }
+        inform("Restored uncommitted changes from before the rebase.");
+        0
+    }
+}
+
+impl Runnable for Rebase {
+    fn run(self) -> i32 {
+        if self.abort {
+            return Rebase::abort();
+        }
+        let keep_pipeline = self.keep_pipeline;
+        let args = match self.make_args() {
+            Ok(args) => args,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let wip_ref = match stash_rebase_wip() {
+            Ok(wip_ref) => wip_ref,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        if !keep_pipeline && wip_ref.is_none() {
+            args.run_exit()
+        }
+        let Ok(status) = make_git_command(&args).status() else {
+            return 1;
+        };
+        if !status.success() {
+            match &wip_ref {
+                // The rebase did not start, so nothing needs to wait for it.
+                Some(wip_ref) if find_operation_in_progress().is_none() => {
+                    if let Err(err) = restore_rebase_wip(wip_ref) {
+                        eprintln!("{}", err);
+                    }
+                }
+                Some(wip_ref) => eprintln!(
+                    "Uncommitted changes were stashed in {}.  \"oaf rebase --abort\" restores \
+                    them, or after completing the rebase, run \"git stash apply {}\".",
+                    wip_ref, wip_ref
+                ),
+                None => (),
+            }
+            if keep_pipeline {
+                inform("After completing the rebase, run \"oaf pipeline --check\".");
+            }
+            return status.code().unwrap_or(1);
+        }
+        if let Some(wip_ref) = &wip_ref {
+            if let Err(err) = restore_rebase_wip(wip_ref) {
+                eprintln!("{}", err);
+                return 1;
+            }
+        }
+        if keep_pipeline {
+            check_pipeline()
+        } else {
+            0
+        }
+    }
+}
+
+impl ArgMaker for Rebase {
+    fn make_args(self) -> Result<Vec<String>, MakeArgsErr> {
+        let target = match self.target {
+            Some(target) => target,
+            None => find_target().map_err(MakeArgsErr::RebaseFindTarget)?.into(),
+        };
+        let mut cmd_args = vec![];
+        if self.autosquash && !self.interactive {
+            // Autosquash requires an interactive rebase, but accepting the todo list unchanged
+            // makes it non-interactive.
+            cmd_args.extend(["-c", "sequence.editor=:"]);
+        }
+        cmd_args.push("rebase");
+        if self.interactive || self.autosquash {
+            cmd_args.push("--interactive");
+        }
+        if self.autosquash {
+            cmd_args.push("--autosquash");
+        }
+        cmd_args.push(&target.spec);
+        Ok(to_strings(&cmd_args))
+    }
+}
+
+#[derive(Debug, Args)]
+/// Show the commit that last modified each line of a file.
+pub struct Blame {
+    /// The commit to annotate the file as of.  (Defaults to working directory.)
+    #[arg(long, short)]
+    source: Option<CommitSpec>,
+    /// Annotate only this range of lines, e.g. "10,20", "/start/,/end/" or ":funcname".  May be
+    /// repeated.
+    #[arg(long = "lines", short = 'L', value_name = "RANGE")]
+    line_ranges: Vec<String>,
+    /// Ignore whitespace changes when finding the commit that modified a line.
+    #[arg(long, short = 'w')]
+    ignore_whitespace: bool,
+    /// Detect lines moved or copied from other files.  Repeat to search more commits.
+    #[arg(long = "find-copies", short = 'C', action = clap::ArgAction::Count)]
+    find_copies: u8,
+    /// The file to annotate.
+    path: String,
+}
+
+impl ArgMaker for Blame {
+    fn make_args(self) -> Result<Vec<String>, MakeArgsErr> {
+        let mut cmd_args = vec!["blame"];
+        if self.ignore_whitespace {
+            cmd_args.push("-w");
+        }
+        cmd_args.extend(std::iter::repeat_n("-C", self.find_copies.into()));
+        for line_range in &self.line_ranges {
+            cmd_args.extend(["-L", line_range]);
+        }
+        let mut cmd_args = to_strings(&cmd_args);
+        cmd_args.extend(self.source.map(|s| s.spec));
+        cmd_args.push("--".to_string());
+        cmd_args.push(self.path);
+        Ok(cmd_args)
+    }
+}
+
+#[enum_dispatch]
+#[derive(Debug, Subcommand)]
+pub enum RewriteCommand {
+    Show,
+    Diff,
+    Log,
+    Pull,
+    PushTags,
+    Restore,
+    Revert,
+    Blame,
+    Reword,
+    Export,
+    Cherry,
+}
+
+#[derive(Debug, Parser)]
+#[command(name = "oaf")]
+pub struct Opt {
+    /// Print extra detail, such as full reference names and the git commands run.
+    #[arg(long, short, conflicts_with = "quiet")]
+    verbose: bool,
+    /// Print only errors, not informational messages.
+    #[arg(long, short)]
+    quiet: bool,
+    #[command(subcommand)]
+    command: NativeCommand,
+}
+
+impl RunExit for Opt {
+    fn run_exit(self) -> ! {
+        if self.quiet {
+            set_verbosity(Verbosity::Quiet);
+        } else if self.verbose {
+            set_verbosity(Verbosity::Verbose);
+        }
+        self.command.run_exit()
+    }
+}
+
+#[enum_dispatch]
+#[derive(Debug, Parser)]
+pub enum NativeCommand {
+    DisconnectBranch,
+    Cat,
     #[command(flatten)]
     RewriteCommand(RewriteCommand),
+    Rebase,
     Commit(CommitCmd),
     IgnoreChanges,
     Push,
@@ -510,23 +1572,54 @@ pub enum NativeCommand {
     FakeMerge,
     Merge,
     MergeDiff,
+    CherryPick,
+    Resolve,
     NextBranch,
+    PrevBranch,
     Pipeline,
     SquashCommit,
     Checkout,
     Status,
     #[command()]
     Ignore,
+    Unignore,
     Revno,
+    Branch,
+    Reset,
+    Tag,
+    RenameBranch,
+    DeleteBranch,
+    InitTarget,
+    PruneMerged,
+    Stash,
+    PruneWip,
 }
 #[derive(Debug, Args)]
 /// Record the current contents of the working tree.
 pub struct CommitCmd {
     #[arg(long, short)]
     message: Option<String>,
-    /// Amend the HEAD commit.
+    /// Take the commit message from this file.  Use "-" to read it from stdin.
+    #[arg(long, short = 'F', value_name = "PATH")]
+    file: Option<String>,
+    /// Amend the HEAD commit.  The original author date is kept, unless --date is supplied.
     #[arg(long)]
     amend: bool,
+    /// Set the author date, e.g. "now" to refresh it when amending.
+    #[arg(long, value_name = "DATE")]
+    date: Option<String>,
+    /// GPG-sign the commit, optionally with a specific key.
+    #[arg(
+        long,
+        short = 'S',
+        value_name = "KEYID",
+        num_args = 0..=1,
+        require_equals = true
+    )]
+    sign: Option<Option<String>>,
+    /// Do not GPG-sign the commit, even if "commit.gpgsign" is set.
+    #[arg(long, conflicts_with = "sign")]
+    no_sign: bool,
     #[arg(long, short)]
     no_verify: bool,
     ///Commit only changes in the index.
@@ -534,24 +1627,122 @@ pub struct CommitCmd {
     no_all: bool,
     #[arg(long)]
     no_strict: bool,
+    /// Interactively choose the changes to commit.  Implies --no-all and --no-strict, since you
+    /// are choosing the contents yourself.
+    #[arg(long, short)]
+    patch: bool,
+    /// Run the formatter configured as "oaf.pre-commit-format" on the files being committed, and
+    /// commit its changes too.  The command is run from the top of the tree, with the files as
+    /// arguments.
+    #[arg(long, conflicts_with = "patch")]
+    format: bool,
+    /// Add a trailer such as "Reviewed-by=J. Random <jrandom@example.com>" to the message.  May
+    /// be repeated.
+    #[arg(long, value_name = "KEY=VALUE")]
+    trailer: Vec<String>,
+    /// Quickly save work in progress, to be amended or squashed later.  Commits with the message
+    /// "WIP", skipping hooks and the untracked-file check.
+    #[arg(long, conflicts_with_all = ["message", "file", "patch", "format"])]
+    wip: bool,
+    /// Commit even if the current branch is listed in "oaf.protected-branches" (a
+    /// comma-separated list of branch names).
+    #[arg(long, short)]
+    force: bool,
+    /// Commit files larger than "oaf.max-file-size" (in bytes, e.g. "10m").
+    #[arg(long)]
+    allow_large: bool,
+    /// How to clean up the commit message, e.g. "verbatim" to keep lines starting with "#".
+    #[arg(long, value_name = "MODE")]
+    cleanup: Option<CleanupMode>,
+}
+
+/// The ways git can clean up a commit message.  See "git help commit".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CleanupMode {
+    /// Strip blank lines, trailing whitespace and "#" comments.
+    Strip,
+    /// Strip blank lines and trailing whitespace, but keep "#" lines.
+    Whitespace,
+    /// Do not change the message at all.
+    Verbatim,
+    /// Like "whitespace", but also remove everything after the scissors line in the editor.
+    Scissors,
+    /// "strip" if the message is edited, and "whitespace" otherwise.
+    Default,
+}
+
+/// Return the current branch, if it is listed in "oaf.protected-branches".
+fn find_protected_branch() -> Result<Option<LocalBranchName>, GitError> {
+    let Some(protected) = get_setting("oaf.protected-branches") else {
+        return Ok(None);
+    };
+    let WorktreeHead::Attached { head, .. } = GitStatus::new()?.head else {
+        return Ok(None);
+    };
+    Ok(protected
+        .split(',')
+        .any(|name| name.trim() == head.branch_name())
+        .then_some(head))
 }
 
 impl ArgMaker for CommitCmd {
     fn make_args(self) -> Result<Vec<String>, MakeArgsErr> {
         let mut cmd_args = vec!["commit"];
-        if !self.no_all {
+        if self.patch {
+            cmd_args.push("--patch")
+        } else if !self.no_all {
             cmd_args.push("--all")
         }
+        if self.message.is_some() && self.file.is_some() {
+            return Err(MakeArgsErr::CommitMessageAndFile);
+        }
         if let Some(message) = &self.message {
             cmd_args.extend(["--message", message]);
         }
+        if let Some(file) = &self.file {
+            cmd_args.extend(["--file", file]);
+        }
+        if self.wip {
+            cmd_args.extend(["--message", "WIP"]);
+        }
         if self.amend {
             cmd_args.push("--amend");
         }
-        if self.no_verify {
+        let date_arg;
+        if let Some(date) = &self.date {
+            date_arg = format!("--date={}", date);
+            cmd_args.push(&date_arg);
+        }
+        let sign_arg;
+        match &self.sign {
+            Some(Some(key)) => {
+                sign_arg = format!("-S{}", key);
+                cmd_args.push(&sign_arg);
+            }
+            Some(None) => cmd_args.push("-S"),
+            None if self.no_sign => cmd_args.push("--no-gpg-sign"),
+            None => (),
+        }
+        if self.no_verify || self.wip {
             cmd_args.push("--no-verify");
         }
-        Ok(to_strings(&cmd_args))
+        let cleanup_arg;
+        if let Some(cleanup) = self.cleanup {
+            cleanup_arg = format!(
+                "--cleanup={}",
+                cleanup.to_possible_value().unwrap().get_name()
+            );
+            cmd_args.push(&cleanup_arg);
+        }
+        let mut cmd_args = to_strings(&cmd_args);
+        for trailer in &self.trailer {
+            cmd_args.push("--trailer".to_string());
+            cmd_args.push(match trailer.split_once('=') {
+                Some((key, value)) => format!("{}: {}", key, value),
+                None => trailer.to_owned(),
+            });
+        }
+        Ok(cmd_args)
     }
 }
 
@@ -572,6 +1763,7 @@ impl<T: Runnable> RunExit for T {
 
 impl RunExit for Vec<String> {
     fn run_exit(self) -> ! {
+        detail(format!("Running git {}", self.join(" ")));
         make_git_command(&self).exec();
         exit(1);
     }
@@ -591,10 +1783,145 @@ impl RunExit for RewriteCommand {
     }
 }
 
+/// Warn about whitespace errors in the changes that are about to be committed.
+fn warn_whitespace_errors(all: bool) {
+    let Ok(tree) = base_tree() else { return };
+    let mut args = vec!["diff", "--check"];
+    if !all {
+        args.push("--cached");
+    }
+    let tree_ref = tree.get_tree_reference();
+    args.push(&tree_ref);
+    if let Err(output) = run_git_command(&args) {
+        eprintln!("Warning: whitespace errors in changes being committed:");
+        eprint!("{}", String::from_utf8_lossy(&output.stdout));
+    }
+}
+
+/// List the files about to be committed, relative to the top of the tree, excluding deletions.
+fn list_committed_files(all: bool) -> Result<Vec<String>, String> {
+    let tree = base_tree().map_err(|e| e.to_string())?;
+    let mut args = vec!["diff", "--name-only", "-z", "--diff-filter=d"];
+    if !all {
+        args.push("--cached");
+    }
+    let tree_ref = tree.get_tree_reference();
+    args.push(&tree_ref);
+    let output = run_git_command(&args).map_err(|e| GitError::from(e).to_string())?;
+    Ok(output_to_string(&output)
+        .split_terminator('\0')
+        .map(|s| s.to_string())
+        .collect())
+}
+
+/// Return the limit set by "oaf.max-file-size", if any.
+fn get_max_file_size() -> Result<Option<u64>, String> {
+    let invalid = || "\"oaf.max-file-size\" must be a size in bytes, e.g. \"10m\".".to_string();
+    match run_config(&["--type=int", "--get", "oaf.max-file-size"]) {
+        Ok(output) => output_to_string(&output)
+            .parse()
+            .map(Some)
+            .map_err(|_| invalid()),
+        Err(ConfigErr::SectionKeyInvalid) => Ok(None),
+        Err(_) => Err(invalid()),
+    }
+}
+
+/// Find the files about to be committed that are larger than the limit, with their sizes.
+///
+/// With `all`, the working files are measured; otherwise the staged blobs are.
+fn find_large_files(all: bool, limit: u64) -> Result<Vec<(String, u64)>, String> {
+    let top = PathBuf::from(get_toplevel().map_err(|e| e.to_string())?);
+    let mut large = vec![];
+    for file in list_committed_files(all)? {
+        let size = if all {
+            fs::metadata(top.join(&file)).map_or(0, |metadata| metadata.len())
+        } else {
+            let output = run_git_command(&["cat-file", "-s", &format!(":{}", file)])
+                .map_err(|e| GitError::from(e).to_string())?;
+            output_to_string(&output).parse().unwrap_or(0)
+        };
+        if size > limit {
+            large.push((file, size));
+        }
+    }
+    Ok(large)
+}
+
+/// Run the configured formatter on the files about to be committed, and stage any changes it
+/// makes.
+fn format_committed_files(all: bool) -> Result<(), String> {
+    let Some(formatter) = get_setting("oaf.pre-commit-format") else {
+        return Err("No formatter configured.  Set one with \
+            \"git config oaf.pre-commit-format <command>\"."
+            .into());
+    };
+    let top = get_toplevel().map_err(|e| e.to_string())?;
+    let files = list_committed_files(all)?;
+    if files.is_empty() {
+        return Ok(());
+    }
+    let top = PathBuf::from(top);
+    let read_all =
+        || -> Vec<Option<Vec<u8>>> { files.iter().map(|f| fs::read(top.join(f)).ok()).collect() };
+    let before = read_all();
+    let status = Command::new("sh")
+        .args(["-c", &format!("{} \"$@\"", formatter), "oaf-format"])
+        .args(&files)
+        .current_dir(&top)
+        .status()
+        .map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err(format!("Formatter \"{}\" failed.", formatter));
+    }
+    let changed: Vec<&String> = files
+        .iter()
+        .zip(before.into_iter().zip(read_all()))
+        .filter(|(_, (old, new))| old != new)
+        .map(|(f, _)| f)
+        .collect();
+    if changed.is_empty() {
+        return Ok(());
+    }
+    inform("Formatting changed these files:");
+    for file in &changed {
+        inform(format!("  {}", file));
+    }
+    let mut add_args = vec!["add", "--"];
+    add_args.extend(changed.iter().map(|f| f.as_str()));
+    let status = make_git_command(&add_args)
+        .current_dir(&top)
+        .status()
+        .map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err("Could not stage the formatted files.".into());
+    }
+    Ok(())
+}
+
 impl Runnable for CommitCmd {
     fn run(self) -> i32 {
-        if !self.no_strict {
-            let status = match GitStatus::new() {
+        // With --patch, the user chooses the contents explicitly, so untracked files are probably
+        // deliberate.  With --wip, speed matters more than completeness.
+        let strict = !self.no_strict && !self.patch && !self.wip;
+        if !self.force {
+            match find_protected_branch() {
+                Ok(None) => (),
+                Ok(Some(branch)) => {
+                    eprintln!(
+                        "{} is a protected branch.  Use --force to commit to it anyway.",
+                        branch.branch_name()
+                    );
+                    return 1;
+                }
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return 1;
+                }
+            }
+        }
+        if strict {
+            let status = match repo_status() {
                 Ok(status) => status,
                 Err(err) => {
                     eprintln!("{}", err);
@@ -607,10 +1934,40 @@ impl Runnable for CommitCmd {
                 for entry in untracked {
                     eprintln!("{}", entry);
                 }
-                eprintln!("You can add them with \"oaf add\", ignore them with \"oaf ignore\", or use --no-strict.");
+                inform("You can add them with \"oaf add\", ignore them with \"oaf ignore\", or use --no-strict.");
+                return 1;
+            }
+        }
+        if self.format {
+            if let Err(err) = format_committed_files(!self.no_all) {
+                eprintln!("{}", err);
                 return 1;
             }
         }
+        if strict {
+            warn_whitespace_errors(!self.no_all);
+        }
+        // With --patch, the files are only chosen once git commit runs.
+        if !self.allow_large && !self.patch {
+            let large = get_max_file_size()
+                .and_then(|limit| limit.map_or(Ok(vec![]), |l| find_large_files(!self.no_all, l)));
+            match large {
+                Ok(large) if large.is_empty() => (),
+                Ok(large) => {
+                    eprintln!("Files larger than \"oaf.max-file-size\" are present:");
+                    for (file, size) in large {
+                        eprintln!("{} ({} bytes)", file, size);
+                    }
+                    inform("Use --allow-large to commit them anyway.");
+                    return 1;
+                }
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return 1;
+                }
+            }
+        }
+        let amend = self.amend;
         let args = match self.make_args() {
             Ok(args) => args,
             Err(err) => {
@@ -618,11 +1975,36 @@ impl Runnable for CommitCmd {
                 return 1;
             }
         };
-        make_git_command(&args).exec();
+        if !amend {
+            make_git_command(&args).exec();
+            return 0;
+        }
+        let Ok(status) = make_git_command(&args).status() else {
+            return 1;
+        };
+        if !status.success() {
+            return status.code().unwrap_or(1);
+        }
+        report_amended();
         0
     }
 }
 
+/// Show the subject and dates of the amended commit, so the effect of the amend can be checked.
+fn report_amended() {
+    let Ok(output) = run_git_command(&[
+        "log",
+        "-1",
+        "--format=Amended: %s%nAuthor date: %ad%nCommit date: %cd",
+    ]) else {
+        return;
+    };
+    inform(output_to_string(&output));
+}
+
+/// The remote to push to when the branch has no upstream and none is supplied.
+const DEFAULT_REMOTE: &str = "origin";
+
 #[derive(Debug, Args)]
 /**
 Transfer local changes to a remote repository and branch.
@@ -633,30 +2015,130 @@ pub struct Push {
     #[arg(long, short)]
     /// Allow changing history on the remote branch
     force: bool,
+    /// Show what would be pushed, without pushing.
+    #[arg(long, short = 'n')]
+    dry_run: bool,
+    /// Push every branch in the current pipeline.  Branches without an upstream get one on the
+    /// repository pushed to.
+    #[arg(long)]
+    pipeline: bool,
     repository: Option<String>,
 }
 
-impl Runnable for Push {
-    fn run(self) -> i32 {
-        let branch = match get_current_branch() {
-            Ok(branch) => branch,
-            Err(unhandled) => {
-                eprintln!("Unhandled: {}", unhandled.name);
+impl Push {
+    fn push_pipeline(self) -> i32 {
+        let repo = match Repository::open_from_env().map_err(OpenRepoError::from) {
+            Ok(repo) => repo,
+            Err(err) => {
+                eprintln!("{}", err);
                 return 1;
             }
         };
-        let mut args = vec!["push"];
-        args.extend(if setting_exists(&branch.setting_name("remote")) {
-            if !setting_exists(&branch.setting_name("merge")) {
-                panic!("Branch in unsupported state");
+        let current = match get_local_current(&repo) {
+            Ok(current) => current,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
             }
-            self.repository.iter().map(|s| s.as_str()).collect()
-        } else {
-            match Commit::from_str("HEAD") {
-                Ok(_) => {
-                    let repo = self.repository.as_deref().unwrap_or("origin");
-                    vec!["-u", repo, "HEAD"]
-                }
+        };
+        let branches = match walk_pipeline(&repo, current) {
+            Ok((branches, _)) => branches,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let remote = self.repository.as_deref().unwrap_or(DEFAULT_REMOTE);
+        let mut args = vec!["push", "--porcelain", remote];
+        if self.force {
+            args.push("--force");
+        }
+        if self.dry_run {
+            args.push("--dry-run");
+        }
+        let refspecs: Vec<String> = branches
+            .iter()
+            .map(|branch| format!("{}:{}", branch.full(), branch.full()))
+            .collect();
+        args.extend(refspecs.iter().map(|s| s.as_str()));
+        let output = match make_git_command(&args).output() {
+            Ok(output) => output,
+            Err(err) => {
+                eprintln!("Could not run git: {}", err);
+                return 1;
+            }
+        };
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        // Each result line is "<flag>\t<from>:<to>\t<summary>".
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let mut fields = line.split('\t').skip(1);
+            let (Some(refs), Some(summary)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let from = refs.split(':').next().unwrap_or(refs);
+            let name = from.strip_prefix("refs/heads/").unwrap_or(from);
+            println!("{}: {}", name, summary);
+        }
+        if !output.status.success() {
+            return output.status.code().unwrap_or(1);
+        }
+        if self.dry_run {
+            return 0;
+        }
+        for branch in &branches {
+            if setting_exists(&branch.setting_name("remote")) {
+                continue;
+            }
+            // As "push -u" does.
+            let result = set_setting(
+                SettingLocation::Local,
+                &branch.setting_name("remote"),
+                remote,
+            )
+            .and_then(|_| {
+                set_setting(
+                    SettingLocation::Local,
+                    &branch.setting_name("merge"),
+                    &branch.full(),
+                )
+            });
+            if let Err(err) = result {
+                eprintln!(
+                    "Could not set upstream for {}: {}",
+                    branch.branch_name(),
+                    err
+                );
+                return 1;
+            }
+        }
+        0
+    }
+}
+
+impl Runnable for Push {
+    fn run(self) -> i32 {
+        if self.pipeline {
+            return self.push_pipeline();
+        }
+        let branch = match get_current_branch() {
+            Ok(branch) => branch,
+            Err(unhandled) => {
+                eprintln!("Unhandled: {}", unhandled.name);
+                return 1;
+            }
+        };
+        let mut args = vec!["push"];
+        args.extend(if setting_exists(&branch.setting_name("remote")) {
+            if !setting_exists(&branch.setting_name("merge")) {
+                panic!("Branch in unsupported state");
+            }
+            self.repository.iter().map(|s| s.as_str()).collect()
+        } else {
+            match Commit::from_str("HEAD") {
+                Ok(_) => {
+                    let repo = self.repository.as_deref().unwrap_or(DEFAULT_REMOTE);
+                    vec!["-u", repo, "HEAD"]
+                }
                 Err(CommitErr::NoCommit { .. }) => {
                     eprintln!("Cannot push: no commits in HEAD.");
                     return 1;
@@ -670,6 +2152,18 @@ impl Runnable for Push {
         if self.force {
             args.push("--force");
         }
+        if self.dry_run {
+            // Wait for git, so that its exit status is returned.
+            args.push("--dry-run");
+            let status = match make_git_command(&args).status() {
+                Ok(status) => status,
+                Err(err) => {
+                    eprintln!("Could not run git: {}", err);
+                    return 1;
+                }
+            };
+            return status.code().unwrap_or(1);
+        }
         make_git_command(&args).exec();
         0
     }
@@ -691,6 +2185,7 @@ impl ArgMaker for PushTags {
 }
 
 #[derive(Debug, Args)]
+#[clap(group(ArgGroup::new("creation").args(&["create", "new_branch"])))]
 /**
 Switch to a branch, stashing and restoring pending changes.
 
@@ -700,41 +2195,228 @@ be stored in a ref named "refs/branch-wip/foo".
 */
 pub struct Switch {
     /// The branch to switch to.
-    branch: String,
+    #[arg(required_unless_present = "recent")]
+    branch: Option<String>,
     /// Create the branch and switch to it
     #[arg(long, short)]
     create: bool,
     /// Switch without stashing/unstashing changes.
     #[arg(long, short)]
     keep: bool,
+    /// When switching to a tag or commit, create a branch with this name there, instead of
+    /// detaching HEAD.  Pending changes are stashed as usual.
+    #[arg(
+        long = "branch",
+        short = 'b',
+        value_name = "NAME",
+        conflicts_with_all = ["create", "keep"]
+    )]
+    new_branch: Option<String>,
+    /// With --create, start the new branch at this branch, tag or commit instead of HEAD.
+    /// Remote branches are accepted, so "--from origin/main --track" tracks origin/main.
+    /// Pending changes are stashed with the current branch, as with --branch, because they may
+    /// not apply at the start point.  (Plain --create starts at HEAD, so they carry over.)
+    #[arg(long, value_name = "START", requires = "create")]
+    from: Option<String>,
+    /// Keep the stashed WIP changes after restoring them, so they can be recovered if the
+    /// restored changes are lost.  Set "oaf.keep-wip" to do this by default.
+    #[arg(long, conflicts_with = "keep")]
+    keep_stash: bool,
+    /// When creating a branch, set its start point as its upstream.  The start point must be a
+    /// branch.
+    #[arg(long, conflicts_with = "no_track", requires = "creation")]
+    track: bool,
+    /// When creating a branch, do not set an upstream, regardless of "branch.autoSetupMerge".
+    #[arg(long, requires = "creation")]
+    no_track: bool,
+    /// Stash untracked files too, so they do not carry over to the target branch.  By default,
+    /// only changes to tracked files are stashed, and untracked files stay in the working tree.
+    #[arg(long, short = 'u', conflicts_with = "keep")]
+    include_untracked: bool,
+    /// Switch even if there are unresolved conflicts.  The conflicted files, including any
+    /// conflict markers, are stashed or kept like other local changes.
+    #[arg(long, short)]
+    force: bool,
+    /// List recently checked-out branches, numbered from 1.  With a number, switch to that
+    /// branch (e.g. "--recent=1" for the previous branch).
+    #[arg(
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        conflicts_with_all = ["branch", "create", "new_branch"]
+    )]
+    recent: Option<Option<usize>>,
+}
+
+/// List local branches by how recently they were checked out, excluding the current branch.
+fn list_recent_others() -> Result<Vec<LocalBranchName>, GitError> {
+    let current = get_current_branch().ok();
+    Ok(list_recent_branches()?
+        .into_iter()
+        .filter(|branch| current.as_ref() != Some(branch))
+        .collect())
+}
+
+/// Convert a name for a local branch (e.g. one to create) into a LocalBranchName, accepting full
+/// reference names.
+fn new_branch_name(name: &str) -> LocalBranchName {
+    LocalBranchName::from_long(name.to_owned(), None).unwrap_or_else(LocalBranchName::from)
+}
+
+/// Explain that switching would overwrite local changes to these files.
+fn report_overwritten_files(files: &[String]) {
+    eprintln!("Switching would overwrite local changes to these files:");
+    for file in files {
+        eprintln!("  {}", file);
+    }
+    eprintln!("Commit them, or switch without --keep so they are stashed.");
+}
+
+fn report_unresolved_conflicts(files: &[String]) {
+    eprintln!("You have unresolved conflicts in these files:");
+    for file in files {
+        eprintln!("  {}", file);
+    }
+    eprintln!("Resolve them or abort the current operation before switching.");
+}
+
+/// Resolve a --from start point to a name git can check out and track.  References keep their
+/// full name, so that tracking applies to them; anything else must be a commit.
+fn resolve_from_point(target: &str) -> Result<String, String> {
+    let repo = Repository::open_from_env()
+        .map_err(OpenRepoError::from)
+        .map_err(|e| e.to_string())?;
+    match BranchyName::UnresolvedName(target.to_owned()).resolve(&repo) {
+        Ok(branchy) => Ok(branchy.get_longest().into_owned()),
+        Err(_) => match Commit::from_str(target) {
+            Ok(commit) => Ok(commit.sha),
+            Err(_) => Err(format!("{} not found", target)),
+        },
+    }
+}
+
+/// Whether a resolved start point is a local or remote branch, which git can track.
+fn is_branch_ref(start_point: &str) -> bool {
+    start_point.starts_with("refs/heads/") || start_point.starts_with("refs/remotes/")
+}
+
+fn report_untrackable(start_point: &str) {
+    eprintln!(
+        "{} is not a branch, so it cannot be tracked.  Omit --track.",
+        start_point
+    );
+}
+
+/// Determine the start point for a new branch.  Only tags and commits are accepted, because
+/// switching to a branch never detaches HEAD.
+fn find_start_point(target: &str) -> Result<Commit, String> {
+    let repo = Repository::open_from_env()
+        .map_err(OpenRepoError::from)
+        .map_err(|e| e.to_string())?;
+    match determine_switch_target(&repo, BranchyName::UnresolvedName(target.to_owned())) {
+        Ok(BranchOrCommit::Commit(commit)) => Ok(commit),
+        Ok(BranchOrCommit::Branch(branch)) => Err(format!(
+            "{} is a branch, so --branch does not apply.",
+            branch.branch_name()
+        )),
+        Err(SwitchErr::NotFound) => Err(format!("{} not found", target)),
+        Err(SwitchErr::GitError(err)) => Err(err.to_string()),
+        Err(err) => Err(format!("{:?}", err)),
+    }
 }
 
 impl Runnable for Switch {
     fn run(self) -> i32 {
+        let branch = match (self.branch, self.recent) {
+            (Some(branch), _) => branch,
+            (None, recent) => {
+                let recent_branches = match list_recent_others() {
+                    Ok(recent_branches) => recent_branches,
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        return 1;
+                    }
+                };
+                let Some(Some(number)) = recent else {
+                    for (number, branch) in recent_branches.iter().enumerate() {
+                        println!("{}: {}", number + 1, branch.branch_name());
+                    }
+                    return 0;
+                };
+                match number
+                    .checked_sub(1)
+                    .and_then(|index| recent_branches.get(index))
+                {
+                    Some(branch) => branch.branch_name().to_owned(),
+                    None => {
+                        eprintln!("No recent branch numbered {}.", number);
+                        return 1;
+                    }
+                }
+            }
+        };
         // Actually a RefName, not a local branch (even if that refname refers to a local branch)
-        let switch_type = if self.create {
+        let switch_type = if let (true, Some(from)) = (self.create, &self.from) {
+            let start_point = match resolve_from_point(from) {
+                Ok(start_point) => start_point,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return 1;
+                }
+            };
+            if self.track && !is_branch_ref(&start_point) {
+                report_untrackable(from);
+                return 1;
+            }
+            SwitchType::CreateFrom(new_branch_name(&branch), start_point)
+        } else if self.create {
             // For creation, any value is a branch name
-            SwitchType::Create(LocalBranchName::from(self.branch.clone()))
+            SwitchType::Create(new_branch_name(&branch))
+        } else if let Some(new_branch) = &self.new_branch {
+            // Only tags and commits are accepted here, so there is no branch to track.
+            if self.track {
+                report_untrackable(&branch);
+                return 1;
+            }
+            let start_point = match find_start_point(&branch) {
+                Ok(start_point) => start_point,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return 1;
+                }
+            };
+            SwitchType::CreateFrom(new_branch_name(new_branch), start_point.sha)
         } else {
-            let target = BranchyName::UnresolvedName(self.branch.clone());
+            let target = BranchyName::UnresolvedName(branch.clone());
             if self.keep {
                 SwitchType::PlainSwitch(target)
             } else {
                 SwitchType::WithStash(target)
             }
         };
-        match stash_switch(switch_type) {
+        let options = SwitchOptions {
+            keep_stash: self.keep_stash || get_bool_setting("oaf.keep-wip"),
+            track: match (self.track, self.no_track) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            },
+            include_untracked: self.include_untracked,
+            force: self.force,
+        };
+        match stash_switch(switch_type, &options) {
             Ok(()) => 0,
             Err(SwitchErr::BranchInUse { path }) => {
-                println!("Branch {} is already in use at {}", self.branch, path);
+                println!("Branch {} is already in use at {}", branch, path);
                 1
             }
             Err(SwitchErr::AlreadyExists) => {
-                eprintln!("Branch {} already exists", self.branch);
+                eprintln!("Branch {} already exists", branch);
                 1
             }
             Err(SwitchErr::NotFound) => {
-                eprintln!("Branch {} not found", self.branch);
+                eprintln!("Branch {} not found", branch);
                 1
             }
             Err(SwitchErr::InvalidBranchName(invalid_branch)) => {
@@ -756,6 +2438,90 @@ impl Runnable for Switch {
                 eprintln!("{}", err);
                 1
             }
+            Err(SwitchErr::OperationInProgress(operation)) => {
+                eprintln!(
+                    "Cannot switch while a {} is in progress.  Complete or abort it first.",
+                    operation
+                );
+                1
+            }
+            Err(SwitchErr::LocalChangesWouldBeOverwritten { files }) => {
+                report_overwritten_files(&files);
+                1
+            }
+            Err(SwitchErr::UnresolvedConflicts { files }) => {
+                report_unresolved_conflicts(&files);
+                1
+            }
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum StashAction {
+    /// List the branches that have stashed WIP changes, with a summary of each stash.
+    List,
+    /// Apply a branch's stashed WIP changes to the working tree, and discard the stash.
+    Apply {
+        /// The branch whose WIP changes should be applied.
+        branch: String,
+        /// Keep the stash after applying it.
+        #[arg(long)]
+        keep: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+/**
+Inspect and recover the WIP changes that "switch" stashes for each branch.
+
+These are normally restored automatically when switching back to the branch, but can be applied
+by hand if a switch fails part-way.
+*/
+pub struct Stash {
+    #[command(subcommand)]
+    action: StashAction,
+}
+
+impl Runnable for Stash {
+    fn run(self) -> i32 {
+        match self.action {
+            StashAction::List => {
+                let output = match run_git_command(&[
+                    "for-each-ref",
+                    "--format=%(refname:lstrip=2): %(subject)",
+                    "refs/branch-wip/",
+                ]) {
+                    Ok(output) => output,
+                    Err(err) => {
+                        eprint!("{}", String::from_utf8_lossy(&err.stderr));
+                        return 1;
+                    }
+                };
+                let listing = output_to_string(&output);
+                if !listing.is_empty() {
+                    println!("{}", listing);
+                }
+                0
+            }
+            StashAction::Apply { branch, keep } => {
+                let target = LocalBranchName::from(branch);
+                match try_apply_wip_stash(&BranchOrCommit::Branch(target.clone()), keep) {
+                    Ok(true) => {
+                        inform(format!("Applied WIP changes for {}", target.branch_name()));
+                        0
+                    }
+                    Ok(false) => {
+                        eprintln!("No WIP changes for {}", target.branch_name());
+                        1
+                    }
+                    Err(err) => {
+                        eprint!("{}", String::from_utf8_lossy(&err.stderr));
+                        eprintln!("The WIP changes for {} were kept.", target.branch_name());
+                        1
+                    }
+                }
+            }
         }
     }
 }
@@ -763,10 +2529,12 @@ impl Runnable for Switch {
 fn handle_switch(switch_type: SwitchType) -> i32 {
     use SwitchType::*;
     let target = match switch_type.clone() {
-        Create(target) | CreateNext(target) => target.branch_name().to_owned(),
+        Create(target) | CreateFrom(target, _) | CreateNext(target) => {
+            target.branch_name().to_owned()
+        }
         PlainSwitch(target) | WithStash(target) => target.get_as_branch().to_string(),
     };
-    match stash_switch(switch_type) {
+    match stash_switch(switch_type, &SwitchOptions::default()) {
         Ok(()) => 0,
         Err(SwitchErr::BranchInUse { path }) => {
             println!("Branch {} is already in use at {}", target, path);
@@ -799,6 +2567,21 @@ fn handle_switch(switch_type: SwitchType) -> i32 {
             eprintln!("{}", err);
             1
         }
+        Err(SwitchErr::OperationInProgress(operation)) => {
+            eprintln!(
+                "Cannot switch while a {} is in progress.  Complete or abort it first.",
+                operation
+            );
+            1
+        }
+        Err(SwitchErr::LocalChangesWouldBeOverwritten { files }) => {
+            report_overwritten_files(&files);
+            1
+        }
+        Err(SwitchErr::UnresolvedConflicts { files }) => {
+            report_unresolved_conflicts(&files);
+            1
+        }
     }
 }
 
@@ -943,6 +2726,427 @@ impl Runnable for DisconnectBranch {
     }
 }
 
+#[derive(Debug, Args)]
+/**
+Delete a branch, along with its pipeline links, WIP changes and remembered target.
+
+If the branch was in the middle of a sequence, the next and previous branches will be connected to
+each other.
+*/
+pub struct DeleteBranch {
+    /// The name of the branch to delete.
+    name: String,
+    /// Delete the branch even if it has commits that are not merged into its remembered target
+    /// (or HEAD, if it has no remembered target).
+    #[arg(long, short)]
+    force: bool,
+}
+
+impl Runnable for DeleteBranch {
+    fn run(self) -> i32 {
+        let repo = match Repository::open_from_env().map_err(OpenRepoError::from) {
+            Ok(repo) => repo,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let branch = new_branch_name(&self.name);
+        if ExtantRefName::resolve(&branch.full()).is_none() {
+            eprintln!("Branch {} not found", branch.branch_name());
+            return 1;
+        }
+        if get_current_branch().is_ok_and(|current| current == branch) {
+            eprintln!("Cannot delete the current branch.");
+            return 1;
+        }
+        let target = match find_target_branchname(branch.clone()) {
+            Ok(target) => target,
+            Err(err) => {
+                eprintln!("Unhandled: {}", err.name);
+                return 1;
+            }
+        };
+        if !self.force {
+            let (base, base_name) = match &target {
+                Some(target) => (
+                    target.full().into_owned(),
+                    target.find_shortest(&repo).into_owned(),
+                ),
+                None => ("HEAD".to_string(), "HEAD".to_string()),
+            };
+            if run_git_command(&["merge-base", "--is-ancestor", &branch.full(), &base]).is_err() {
+                eprintln!(
+                    "{} has commits that are not merged into {}.  Use --force to delete it anyway.",
+                    branch.branch_name(),
+                    base_name
+                );
+                return 1;
+            }
+        }
+        if let Err(err) = remove_branch(&repo, &branch, target.as_ref()) {
+            eprintln!("{}", err);
+            return 1;
+        }
+        inform(format!("Deleted branch {}.", branch.branch_name()));
+        0
+    }
+}
+
+/// Delete a branch, its pipeline links, WIP changes and remembered target.
+///
+/// Branches that targeted it are retargeted to `target`.
+fn remove_branch(
+    repo: &Repository,
+    branch: &LocalBranchName,
+    target: Option<&BranchName>,
+) -> Result<(), String> {
+    // The branch may not be in a pipeline.
+    let _ = unlink_branch(repo, branch);
+    let wip = WipReference::from(&BranchOrCommit::Branch(branch.clone()));
+    if wip.eval().is_ok() && delete_ref(&wip.full()).is_err() {
+        return Err(format!("Could not delete {}", wip.full()));
+    }
+    let target_setting = format!("branch.{}.oaf-target-branch", branch.branch_name());
+    if setting_exists(&target_setting) {
+        run_config(&["--local", "--unset", &target_setting])
+            .expect("Could not unset target branch.");
+    }
+    // Branches that targeted this one now target its target.
+    if let Some(target) = target {
+        retarget_branches(branch, target);
+    }
+    if let Err(err) = run_git_command(&["branch", "-D", branch.branch_name()]) {
+        return Err(String::from_utf8_lossy(&err.stderr).trim_end().to_string());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+/**
+List (or delete) branches that are merged into their remembered target.
+
+Branches with no remembered target are skipped, unless --into is given.  With --delete, each
+merged branch is deleted along with its pipeline links and WIP changes, as with "delete-branch".
+*/
+pub struct PruneMerged {
+    /// Check branches against this branch, instead of their remembered targets.
+    #[arg(long, value_name = "BRANCH")]
+    into: Option<String>,
+    /// Delete the merged branches, instead of just listing them.
+    #[arg(long)]
+    delete: bool,
+}
+
+impl Runnable for PruneMerged {
+    fn run(self) -> i32 {
+        let repo = match Repository::open_from_env().map_err(OpenRepoError::from) {
+            Ok(repo) => repo,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let into = match self.into {
+            None => None,
+            Some(into_name) => {
+                let Some(ExtantRefName { name: Ok(into), .. }) = ExtantRefName::resolve(&into_name)
+                else {
+                    eprintln!("{} is not a branch.", into_name);
+                    return 1;
+                };
+                Some(into)
+            }
+        };
+        let branches = match list_local_branches() {
+            Ok(branches) => branches,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let current = get_current_branch().ok();
+        let mut merged = vec![];
+        for branch in branches {
+            if current.as_ref() == Some(&branch) {
+                continue;
+            }
+            let base = match &into {
+                Some(into) => into.clone(),
+                None => match find_target_branchname(branch.clone()) {
+                    Ok(Some(target)) => target,
+                    _ => continue,
+                },
+            };
+            if base.full() == branch.full() {
+                continue;
+            }
+            if run_git_command(&["merge-base", "--is-ancestor", &branch.full(), &base.full()])
+                .is_ok()
+            {
+                merged.push((branch, base));
+            }
+        }
+        if !self.delete {
+            for (branch, base) in &merged {
+                println!(
+                    "{} (merged into {})",
+                    branch.branch_name(),
+                    base.find_shortest(&repo)
+                );
+            }
+            if !merged.is_empty() {
+                inform("Use --delete to delete them.");
+            }
+            return 0;
+        }
+        let mut result = 0;
+        for (branch, base) in &merged {
+            if let Err(err) = remove_branch(&repo, branch, Some(base)) {
+                eprintln!("{}", err);
+                result = 1;
+                continue;
+            }
+            inform(format!("Deleted branch {}.", branch.branch_name()));
+        }
+        result
+    }
+}
+
+#[derive(Debug, Args)]
+/**
+Delete WIP changes and pipeline links left behind by deleted branches.
+
+Branches deleted with plain git keep their "refs/branch-wip/", "refs/pipe-next/" and
+"refs/pipe-prev/" references.  These are deleted, as are pipeline links to deleted branches.
+*/
+pub struct PruneWip {
+    /// List the references that would be deleted, without deleting them.
+    #[arg(long, short = 'n')]
+    dry_run: bool,
+}
+
+/// Find the WIP and pipeline references whose branch no longer exists, or which link to a branch
+/// that no longer exists.
+fn find_orphan_refs(repo: &Repository) -> Result<Vec<String>, git2::Error> {
+    let mut orphans = vec![];
+    for namespace in ["refs/branch-wip/", "refs/pipe-next/", "refs/pipe-prev/"] {
+        for reference in repo.references_glob(&format!("{}*", namespace))? {
+            let reference = reference?;
+            let Some(name) = reference.name() else {
+                continue;
+            };
+            let branch = LocalBranchName::from(name[namespace.len()..].to_string());
+            if repo.find_reference(&branch.full()).is_err() || reference.resolve().is_err() {
+                orphans.push(name.to_string());
+            }
+        }
+    }
+    Ok(orphans)
+}
+
+impl Runnable for PruneWip {
+    fn run(self) -> i32 {
+        let repo = match Repository::open_from_env().map_err(OpenRepoError::from) {
+            Ok(repo) => repo,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let orphans = match find_orphan_refs(&repo) {
+            Ok(orphans) => orphans,
+            Err(err) => {
+                eprintln!("{}", err.message());
+                return 1;
+            }
+        };
+        for orphan in orphans {
+            if self.dry_run {
+                println!("Would delete {}", orphan);
+            } else if delete_ref(&orphan).is_err() {
+                eprintln!("Could not delete {}", orphan);
+                return 1;
+            } else {
+                inform(format!("Deleted {}", orphan));
+            }
+        }
+        0
+    }
+}
+
+#[derive(Debug, Args)]
+/**
+Rename a branch, keeping its pipeline links, WIP changes and remembered target.
+
+With one name, the current branch is renamed to it.
+*/
+#[command(allow_missing_positional = true)]
+pub struct RenameBranch {
+    /// The branch to rename.  (Defaults to the current branch.)
+    old: Option<String>,
+    /// The new name for the branch.
+    new: String,
+}
+
+impl Runnable for RenameBranch {
+    fn run(self) -> i32 {
+        let repo = match Repository::open_from_env().map_err(OpenRepoError::from) {
+            Ok(repo) => repo,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let old = match self.old {
+            Some(old) => new_branch_name(&old),
+            None => match get_local_current(&repo) {
+                Ok(current) => current,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return 1;
+                }
+            },
+        };
+        let new = new_branch_name(&self.new);
+        // This also renames the branch's settings, including its remembered target.
+        if let Err(err) = run_git_command(&["branch", "-m", old.branch_name(), new.branch_name()]) {
+            eprint!("{}", String::from_utf8_lossy(&err.stderr));
+            return 1;
+        }
+        if let Err(err) = rename_links(&repo, &old, &new) {
+            eprintln!("{}", err);
+            return 1;
+        }
+        let old_wip = WipReference::from(&BranchOrCommit::Branch(old.clone()));
+        if let Ok(oid) = old_wip.eval() {
+            let new_wip = WipReference::from(&BranchOrCommit::Branch(new.clone()));
+            if upsert_ref(&new_wip.full(), &oid).is_err() || delete_ref(&old_wip.full()).is_err() {
+                eprintln!("Could not move WIP changes to {}", new_wip.full());
+                return 1;
+            }
+        }
+        retarget_branches(&old, &new);
+        inform(format!("Renamed branch to {}.", new.branch_name()));
+        0
+    }
+}
+
+#[derive(Debug, Args)]
+/**
+Set a remembered target for every local branch that lacks one.
+
+A branch whose upstream is a different branch (e.g. the branch it was created from) targets its
+upstream.  Other branches target the default branch, i.e. the branch that "origin/HEAD" refers
+to, or else "init.defaultBranch", "main" or "master".
+*/
+pub struct InitTarget {
+    /// Show the targets that would be set, without setting them.
+    #[arg(long, short = 'n')]
+    dry_run: bool,
+}
+
+/// Find the local branch that is the repository's default branch, if any.
+fn find_default_branch() -> Option<LocalBranchName> {
+    let remote_prefix = format!("refs/remotes/{}/", DEFAULT_REMOTE);
+    let remote_head =
+        run_git_command(&["symbolic-ref", "--quiet", &format!("{}HEAD", remote_prefix)])
+            .ok()
+            .and_then(|output| {
+                output_to_string(&output)
+                    .strip_prefix(&remote_prefix)
+                    .map(|name| name.to_owned())
+            });
+    remote_head
+        .into_iter()
+        .chain(get_setting("init.defaultBranch"))
+        .chain(["main".to_string(), "master".to_string()])
+        .map(LocalBranchName::from)
+        .find(|branch| ExtantRefName::resolve(&branch.full()).is_some())
+}
+
+/// Return the branch's upstream, unless it is the same branch on a remote.
+fn find_distinct_upstream(branch: &LocalBranchName) -> Option<BranchName> {
+    let output = run_git_command(&[
+        "rev-parse",
+        "--symbolic-full-name",
+        &format!("{}@{{upstream}}", branch.branch_name()),
+    ])
+    .ok()?;
+    let upstream = BranchName::from_str(&output_to_string(&output)).ok()?;
+    let upstream_name = match &upstream {
+        BranchName::Local(local) => local.branch_name(),
+        BranchName::Remote(remote) => &remote.name,
+    };
+    (upstream_name != branch.branch_name()).then_some(upstream)
+}
+
+impl Runnable for InitTarget {
+    fn run(self) -> i32 {
+        let repo = match Repository::open_from_env().map_err(OpenRepoError::from) {
+            Ok(repo) => repo,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let branches = match list_local_branches() {
+            Ok(branches) => branches,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let default_branch = find_default_branch();
+        for branch in branches {
+            if setting_exists(&target_branch_setting(&branch).to_setting_string()) {
+                continue;
+            }
+            let target = match find_distinct_upstream(&branch) {
+                Some(upstream) => upstream,
+                None => match &default_branch {
+                    Some(default_branch) if *default_branch != branch => {
+                        BranchName::Local(default_branch.clone())
+                    }
+                    _ => continue,
+                },
+            };
+            let target_name = target.find_shortest(&repo);
+            if self.dry_run {
+                println!(
+                    "Would set target of {} to {}",
+                    branch.branch_name(),
+                    target_name
+                );
+                continue;
+            }
+            set_target(&branch, &target).expect("Could not set target branch.");
+            println!("Set target of {} to {}", branch.branch_name(), target_name);
+        }
+        0
+    }
+}
+
+/// Update branches that remember the old branch as their target to use the new branch.
+fn retarget_branches(old: &LocalBranchName, new: &impl ReferenceSpec) {
+    let Ok(output) = run_config(&[
+        "--local",
+        "--get-regexp",
+        r"^branch\..*\.oaf-target-branch$",
+    ]) else {
+        return;
+    };
+    for line in output_to_string(&output).lines() {
+        let Some((key, value)) = line.split_once(' ') else {
+            continue;
+        };
+        if value == old.full() {
+            set_setting(SettingLocation::Local, key, &new.full())
+                .expect("Could not set target branch.");
+        }
+    }
+}
+
 #[derive(Debug, Args)]
 /**
 View and / or set the next branch.
@@ -1025,12 +3229,265 @@ impl Runnable for NextBranch {
     }
 }
 
+#[derive(Debug, Args)]
+/**
+View and / or set the previous branch.
+
+See also "pipeline".
+*/
+pub struct PrevBranch {
+    /// The branch to set as the previous branch
+    prev: Option<String>,
+}
+
+impl Runnable for PrevBranch {
+    fn run(self) -> i32 {
+        let repo = match Repository::open_from_env().map_err(OpenRepoError::from) {
+            Ok(repo) => repo,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let current = match get_local_current(&repo) {
+            Err(err) => {
+                println!("{}", err);
+                return 1;
+            }
+            Ok(current) => current,
+        };
+        let Some(prev_name) = self.prev else {
+            match resolve_symbolic_reference(&repo, &PipePrev::from(current)) {
+                Ok(prev) => {
+                    println!("{}", prev.find_shortest(&repo));
+                    return 0;
+                }
+                Err(RefErr::NotFound(_)) => {
+                    eprintln!("No previous branch");
+                    return 0;
+                }
+                Err(err) => {
+                    eprintln!("{}", PrevRefErr(err));
+                    return 1;
+                }
+            }
+        };
+        let prev = match repo
+            .resolve_reference_from_short_name(&prev_name)
+            .map_err(RefErr::from)
+        {
+            Ok(prev) => prev,
+            Err(RefErr::NotFound(_)) => {
+                eprintln!("{} does not exist", prev_name);
+                return 1;
+            }
+            Err(RefErr::Other(err)) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+            Err(err) => {
+                println!("{}", PrevRefErr(err));
+                return 1;
+            }
+        };
+        let prev_branch = match LocalBranchName::try_from(&prev) {
+            Ok(prev_branch) => prev_branch,
+            Err(BranchValidationError::NotLocalBranch(_)) => {
+                eprintln!("Not a local branch: {}", prev_name);
+                return 1;
+            }
+            Err(BranchValidationError::NotUtf8(_)) => {
+                eprintln!("Not a utf8 string: {}", prev_name);
+                return 1;
+            }
+        };
+        if let Err(err) =
+            check_link_branches(&repo, prev_branch.into(), current.into()).map(|x| x.link(&repo))
+        {
+            eprintln!("{}", err);
+            return 1;
+        }
+        0
+    }
+}
+
 /// List a branch sequence
+///
+/// Each branch shows the number of commits it is ahead of (and behind) the previous branch.
 #[derive(Debug, Args)]
-pub struct Pipeline {}
+pub struct Pipeline {
+    /// Check that all pipeline links refer to existing branches, and are linked in both
+    /// directions, instead of listing the branch sequence.
+    #[arg(long, conflicts_with = "format")]
+    check: bool,
+    /// List every pipeline in the repository, not just the one containing the current branch.
+    #[arg(long, conflicts_with = "check")]
+    all: bool,
+    /// The output format.  "json" emits an array of objects with "name" and "current" fields.
+    /// (With --all, an array of such arrays.)
+    #[arg(long, value_enum, default_value_t = PipelineFormat::Text)]
+    format: PipelineFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum PipelineFormat {
+    Text,
+    Json,
+}
+
+/// A branch in the JSON output of the pipeline command.
+#[derive(Serialize)]
+struct PipelineEntry<'a> {
+    name: &'a str,
+    current: bool,
+}
+
+/// Report any problems with the pipeline links.  Returns the exit code.
+fn check_pipeline() -> i32 {
+    let repo = match Repository::open_from_env().map_err(OpenRepoError::from) {
+        Ok(repo) => repo,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 1;
+        }
+    };
+    match check_links(&repo) {
+        Ok(problems) if problems.is_empty() => {
+            inform("Pipeline links are consistent.");
+            0
+        }
+        Ok(problems) => {
+            for problem in problems {
+                eprintln!("{}", problem);
+            }
+            1
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+/// Count the first-parent commits on the branch and its previous branch since they diverged.
+fn count_ahead_behind(
+    repo: &Repository,
+    prev: &LocalBranchName,
+    branch: &LocalBranchName,
+) -> Result<(i32, i32), String> {
+    let prev = Commit::from_str(&prev.full()).map_err(|e| e.to_string())?;
+    let branch = Commit::from_str(&branch.full()).map_err(|e| e.to_string())?;
+    let base = calc_revno(repo, &branch.find_merge_base(&prev)).map_err(|e| e.to_string())?;
+    let ahead = calc_revno(repo, &branch).map_err(|e| e.to_string())? - base;
+    let behind = calc_revno(repo, &prev).map_err(|e| e.to_string())? - base;
+    Ok((ahead, behind))
+}
+
+/// Return the pipeline containing the branch, and the index of the branch within it.
+fn walk_pipeline(
+    repo: &Repository,
+    branch: LocalBranchName,
+) -> Result<(Vec<LocalBranchName>, usize), RefErr> {
+    let mut branches = vec![];
+    let mut loop_lb = advance::<PipePrev>(repo, branch.clone());
+    while let Some(tmp) = loop_lb? {
+        branches.push(tmp.clone());
+        loop_lb = advance::<PipePrev>(repo, tmp);
+    }
+    branches.reverse();
+    let index = branches.len();
+    branches.push(branch.clone());
+    let mut loop_lb = advance::<PipeNext>(repo, branch);
+    while let Some(tmp) = loop_lb? {
+        branches.push(tmp.clone());
+        loop_lb = advance::<PipeNext>(repo, tmp);
+    }
+    Ok((branches, index))
+}
+
+/// List a pipeline's branches for JSON output.
+fn pipeline_entries(
+    branches: &[LocalBranchName],
+    current_index: Option<usize>,
+) -> Vec<PipelineEntry<'_>> {
+    branches
+        .iter()
+        .enumerate()
+        .map(|(index, branch)| PipelineEntry {
+            name: branch.branch_name(),
+            current: Some(index) == current_index,
+        })
+        .collect()
+}
+
+fn print_pipeline(repo: &Repository, branches: &[LocalBranchName], current_index: Option<usize>) {
+    for (index, branch) in branches.iter().enumerate() {
+        let marker = if Some(index) == current_index {
+            "*"
+        } else {
+            " "
+        };
+        let counts = match index.checked_sub(1).map(|prev| &branches[prev]) {
+            None => "".to_string(),
+            Some(prev) => match count_ahead_behind(repo, prev, branch) {
+                Ok((ahead, 0)) => format!(" (+{})", ahead),
+                Ok((ahead, behind)) => format!(" (+{}, -{})", ahead, behind),
+                // A branch without commits has no counts, but is still listed.
+                Err(_) => "".to_string(),
+            },
+        };
+        println!("{} {}{}", marker, branch.branch_name(), counts);
+    }
+}
+
+impl Pipeline {
+    fn run_all(self, repo: &Repository) -> i32 {
+        let heads = match find_pipeline_heads(repo) {
+            Ok(heads) => heads,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let current = get_local_current(repo).ok();
+        let mut pipelines = vec![];
+        for head in heads {
+            let branches = match walk_pipeline(repo, head) {
+                Ok((branches, _)) => branches,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return 1;
+                }
+            };
+            let current_index = branches.iter().position(|b| Some(b) == current.as_ref());
+            pipelines.push((branches, current_index));
+        }
+        if self.format == PipelineFormat::Json {
+            let pipelines: Vec<_> = pipelines
+                .iter()
+                .map(|(branches, current_index)| pipeline_entries(branches, *current_index))
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string(&pipelines).expect("Could not format pipelines.")
+            );
+            return 0;
+        }
+        for (index, (branches, current_index)) in pipelines.iter().enumerate() {
+            if index > 0 {
+                println!();
+            }
+            print_pipeline(repo, branches, *current_index);
+        }
+        0
+    }
+}
 
 impl Runnable for Pipeline {
     fn run(self) -> i32 {
+        if self.check {
+            return check_pipeline();
+        }
         let repo = match Repository::open_from_env().map_err(OpenRepoError::from) {
             Ok(repo) => repo,
             Err(err) => {
@@ -1038,6 +3495,9 @@ impl Runnable for Pipeline {
                 return 1;
             }
         };
+        if self.all {
+            return self.run_all(&repo);
+        }
         let current_lb = match get_local_current(&repo) {
             Err(err) => {
                 println!("{}", err);
@@ -1045,38 +3505,22 @@ impl Runnable for Pipeline {
             }
             Ok(current) => current,
         };
-        let mut previous = vec![];
-        let mut loop_lb = advance::<PipePrev>(&repo, current_lb.clone());
-        loop {
-            let tmp = match loop_lb {
-                Err(_) => {
-                    eprintln!("Error!");
-                    return 1;
-                }
-                Ok(Some(current)) => current,
-                Ok(None) => break,
-            };
-            previous.push(tmp.branch_name().to_owned());
-            loop_lb = advance::<PipePrev>(&repo, tmp);
-        }
-        previous.reverse();
-        for branch in previous {
-            println!("  {}", branch);
-        }
-        println!("* {}", current_lb.branch_name());
-        let mut loop_lb = advance::<PipeNext>(&repo, current_lb);
-        loop {
-            let tmp = match loop_lb {
-                Err(_) => {
-                    eprintln!("Error!");
-                    return 1;
-                }
-                Ok(Some(current)) => current,
-                Ok(None) => break,
-            };
-            println!("  {}", tmp.branch_name());
-            loop_lb = advance::<PipeNext>(&repo, tmp);
+        let (branches, current_index) = match walk_pipeline(&repo, current_lb) {
+            Ok(pipeline) => pipeline,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        if self.format == PipelineFormat::Json {
+            let entries = pipeline_entries(&branches, Some(current_index));
+            println!(
+                "{}",
+                serde_json::to_string(&entries).expect("Could not format pipeline.")
+            );
+            return 0;
         }
+        print_pipeline(&repo, &branches, Some(current_index));
         0
     }
 }
@@ -1175,10 +3619,139 @@ impl Runnable for SquashCommit {
             .commit(&parent, None, message)
             .expect("Could not generate commit.");
         fm_commit.set_wt_head();
-        eprintln!("Commit squashed.  To undo: oaf reset {}", head.sha);
+        inform(format!("Commit squashed.  To undo: oaf reset {}", head.sha));
+        0
+    }
+}
+#[derive(Debug, Args)]
+/**
+Move the current branch to a different commit.
+
+By default, the working tree and index are unchanged, so the differences appear as uncommitted
+changes.
+*/
+pub struct Reset {
+    /// The commit to move the branch to.
+    commit: CommitSpec,
+    /// Also reset the index and working tree, discarding any uncommitted changes.
+    #[arg(long)]
+    hard: bool,
+}
+
+impl Runnable for Reset {
+    fn run(self) -> i32 {
+        let status = match GitStatus::new() {
+            Ok(status) => status,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        if status
+            .iter()
+            .any(|se| matches!(se.state, EntryState::Unmerged { .. }))
+        {
+            eprintln!("Cannot reset while there are unmerged files.  Resolve the conflicts first.");
+            return 1;
+        }
+        let sha = &self.commit.as_ref().sha;
+        if !self.hard {
+            set_head(sha);
+            return 0;
+        }
+        let Ok(status) = make_git_command(&["reset", "--hard", sha]).status() else {
+            return 1;
+        };
+        status.code().unwrap_or(1)
+    }
+}
+
+#[derive(Debug, Args)]
+/**
+Create a tag, or list tags.
+
+Tags are listed in version order, e.g. "v1.10" after "v1.9".
+*/
+pub struct Tag {
+    /// The name of the tag to create.  If omitted, tags are listed.
+    name: Option<String>,
+    /// The commit to tag.  Defaults to HEAD.
+    #[arg(requires = "name")]
+    commit: Option<CommitSpec>,
+    /// Create an annotated tag with this message.
+    #[arg(long, short, requires = "name")]
+    message: Option<String>,
+    /// After creating the tag, push it (and no other tags) to REMOTE.  Defaults to "origin".
+    #[arg(
+        long,
+        value_name = "REMOTE",
+        num_args = 0..=1,
+        require_equals = true,
+        requires = "name"
+    )]
+    push: Option<Option<String>>,
+    /// List only tags that are reachable from this commit.
+    #[arg(long, value_name = "COMMIT", conflicts_with = "name")]
+    merged: Option<CommitSpec>,
+    /// List only tags that are not reachable from this commit.
+    #[arg(long, value_name = "COMMIT", conflicts_with = "name")]
+    no_merged: Option<CommitSpec>,
+    /// List only tags that contain this commit, e.g. the releases that include a fix.
+    #[arg(long, value_name = "COMMIT", conflicts_with = "name")]
+    contains: Option<CommitSpec>,
+    /// List only tags that do not contain this commit.
+    #[arg(long, value_name = "COMMIT", conflicts_with = "name")]
+    no_contains: Option<CommitSpec>,
+}
+
+impl Runnable for Tag {
+    fn run(self) -> i32 {
+        let Some(name) = self.name else {
+            let mut args = to_strings(&["tag", "--list", "--sort=v:refname"]);
+            for (option, commit) in [
+                ("--merged", self.merged),
+                ("--no-merged", self.no_merged),
+                ("--contains", self.contains),
+                ("--no-contains", self.no_contains),
+            ] {
+                if let Some(commit) = commit {
+                    args.extend([option.to_string(), commit.spec]);
+                }
+            }
+            let Ok(status) = make_git_command(&args).status() else {
+                return 1;
+            };
+            return status.code().unwrap_or(1);
+        };
+        let mut args = vec!["tag"];
+        if let Some(message) = &self.message {
+            args.extend(["--message", message]);
+        }
+        args.push(&name);
+        args.extend(self.commit.as_ref().map(|c| c.spec.as_str()));
+        let Ok(status) = make_git_command(&args).status() else {
+            return 1;
+        };
+        if !status.success() {
+            return status.code().unwrap_or(1);
+        }
+        let Some(remote) = self.push else {
+            return 0;
+        };
+        let remote = remote.as_deref().unwrap_or(DEFAULT_REMOTE);
+        let tag_ref = format!("refs/tags/{}", name);
+        let Ok(status) = make_git_command(&["push", remote, &tag_ref]).status() else {
+            return 1;
+        };
+        if !status.success() {
+            eprintln!("Created tag {}, but could not push it to {}.", name, remote);
+            return status.code().unwrap_or(1);
+        }
+        inform(format!("Pushed tag {} to {}.", name, remote));
         0
     }
 }
+
 #[derive(Debug, Args)]
 /// Disabled to prevent accidentally discarding stashed changes.
 pub struct Checkout {
@@ -1199,43 +3772,252 @@ impl Runnable for Checkout {
 
 #[derive(Debug, Args)]
 /// Show the status of changed and unknown files in the working tree.
-pub struct Status {}
+pub struct Status {
+    /// Group files into sections: staged, not staged, unmerged and untracked.  A partially-staged
+    /// file appears in both the staged and not staged sections.
+    #[arg(long)]
+    grouped: bool,
+    /// Do not show the branch and upstream information, only the files.
+    #[arg(long, conflicts_with = "branch_only")]
+    no_branch: bool,
+    /// Show only the branch and upstream information, not the files.
+    #[arg(long, conflicts_with = "grouped")]
+    branch_only: bool,
+    /// Print nothing.  Exit with 0 if there are no changes or untracked files, 1 if there are,
+    /// and 2 on error.
+    #[arg(
+        long = "exit-code",
+        short = 'q',
+        visible_alias = "quiet",
+        conflicts_with_all = ["grouped", "no_branch", "branch_only"]
+    )]
+    exit_code: bool,
+    /// Print one line per file in a stable format for scripts: the two status characters, a tab
+    /// and the path relative to the top of the tree.  For renames, a tab and the old path
+    /// follow.  No branch information is shown.
+    #[arg(
+        long,
+        conflicts_with_all = ["grouped", "no_branch", "branch_only", "exit_code"]
+    )]
+    porcelain: bool,
+}
+
+/// Print status entries in sections, like "git status" does.
+fn print_grouped(entries: &[StatusEntry], current_dir: &Path) {
+    let mut staged = vec![];
+    let mut unstaged = vec![];
+    let mut unmerged = vec![];
+    let mut untracked = vec![];
+    for se in entries {
+        match se.state {
+            EntryState::Changed {
+                staged_status,
+                tree_status,
+            }
+            | EntryState::Renamed {
+                staged_status,
+                tree_status,
+                ..
+            } => {
+                if staged_status != EntryLocationStatus::Unmodified {
+                    staged.push(se);
+                }
+                if tree_status != EntryLocationStatus::Unmodified {
+                    unstaged.push(se);
+                }
+            }
+            EntryState::Unmerged { .. } => unmerged.push(se),
+            EntryState::Untracked => untracked.push(se),
+            EntryState::Ignored => (),
+        }
+    }
+    for (heading, section) in [
+        ("Changes to be committed:", staged),
+        ("Changes not staged for commit:", unstaged),
+        ("Unmerged paths:", unmerged),
+        ("Untracked files:", untracked),
+    ] {
+        if section.is_empty() {
+            continue;
+        }
+        println!("{}", heading);
+        for se in section {
+            println!("  {}", se.format_entry(&current_dir));
+        }
+    }
+}
+
+/// Print the current branch, how it relates to its upstream, and any stranded WIP.
+fn print_branch_header(head: &WorktreeHead) {
+    match head {
+        WorktreeHead::Attached {
+            commit: BranchCommit::Initial,
+            head,
+            ..
+        } => {
+            // Fall back to HEAD's symref target if status did not provide a name.
+            let name = match Repository::open_from_env().map(|repo| get_local_current(&repo)) {
+                Ok(Ok(branch)) if head.branch_name().is_empty() => branch,
+                _ => head.clone(),
+            };
+            println!("On branch {} — no commits yet", name.branch_name());
+        }
+        WorktreeHead::Attached { head, upstream, .. } => {
+            println!("On branch {}", head.branch_name());
+            if let Some(upstream) = upstream {
+                let msg = match (upstream.added, upstream.removed) {
+                    (0, 0) => format!("Your branch is up to date with '{}'.", upstream.name),
+                    (0, removed) => format!(
+                        "Your branch is behind '{}' by {} commit(s), and can be \
+                        fast-forwarded.",
+                        upstream.name, removed
+                    ),
+                    (added, 0) => format!(
+                        "Your branch is ahead of '{}' by {} commit(s).",
+                        upstream.name, added
+                    ),
+                    (added, removed) => format!(
+                        "Your branch and '{}' have diverged,\n\
+                    and have {} and {} different commits each, respectively.\n  \
+                    (use \"oaf merge {}\" to merge the remote branch into yours)",
+                        upstream.name, added, removed, upstream.name
+                    ),
+                };
+                println!("{}", msg);
+                if upstream.is_rewritten() {
+                    println!(
+                        "Warning: '{}' was rewritten, and no longer contains commits your \
+                        branch is based on.\n  \
+                        (check what changed upstream before merging or rebasing)",
+                        upstream.name
+                    );
+                }
+            }
+            print_target(head, upstream.as_ref());
+            // With oaf.keep-wip, WIP references are expected to outlive the switch.
+            let wip_ref = WipReference::from(&BranchOrCommit::Branch(head.clone()));
+            if wip_ref.eval().is_ok() && !get_bool_setting("oaf.keep-wip") {
+                inform(format!(
+                    "You have stashed WIP changes for this branch; run 'oaf switch {}' to \
+                    restore them",
+                    head.branch_name()
+                ));
+            }
+        }
+        WorktreeHead::Detached(commit) => {
+            println!("HEAD detached at {}", describe_detached(commit))
+        }
+    }
+}
+
+/// Name a detached commit by a tag or remote branch pointing at it, or else by its short sha.
+fn describe_detached(commit: &str) -> String {
+    let name = run_git_command(&[
+        "for-each-ref",
+        "--points-at",
+        commit,
+        "--format=%(refname:short)",
+        "refs/tags/",
+        "refs/remotes/",
+    ])
+    .ok()
+    .and_then(|output| output_to_string(&output).lines().next().map(String::from));
+    name.or_else(|| {
+        run_git_command(&["rev-parse", "--short", commit])
+            .ok()
+            .map(|output| output_to_string(&output))
+    })
+    .unwrap_or_else(|| commit.to_string())
+}
+
+/// Print the remembered target, if it is not simply the upstream.
+fn print_target(head: &LocalBranchName, upstream: Option<&UpstreamInfo>) {
+    let Ok(repo) = Repository::open_from_env() else {
+        return;
+    };
+    let Ok(Some(target)) = find_target_branchname(head.clone()) else {
+        return;
+    };
+    let target = target.find_shortest(&repo);
+    match upstream {
+        None => println!("Merging into '{}'.", target),
+        Some(upstream) if upstream.name != target => {
+            println!("Tracking '{}', merging into '{}'.", upstream.name, target)
+        }
+        Some(_) => (),
+    }
+}
+
+/// Note any linked worktrees, and warn if the current branch is also checked out in one.
+fn print_worktree_footer(head: &WorktreeHead) {
+    let Ok(top) = get_toplevel() else { return };
+    let top = PathBuf::from(top).canonicalize().ok();
+    let others: Vec<_> = list_worktree()
+        .into_iter()
+        .filter(|wt| PathBuf::from(&wt.path).canonicalize().ok() != top)
+        .collect();
+    if others.is_empty() {
+        return;
+    }
+    let plural = if others.len() == 1 { "" } else { "s" };
+    println!("{} linked worktree{}", others.len(), plural);
+    let WorktreeHead::Attached { head, .. } = head else {
+        return;
+    };
+    for wt in others {
+        let branch = match wt.state {
+            WorktreeState::UncommittedBranch { branch } => branch,
+            WorktreeState::CommittedBranch { branch, .. } => branch,
+            WorktreeState::DetachedHead { .. } => continue,
+        };
+        if &branch == head {
+            eprintln!(
+                "Warning: {} is also checked out at {}",
+                head.branch_name(),
+                wt.path
+            );
+        }
+    }
+}
+
+/// Return the status of the current repository, without running a git subprocess.
+fn repo_status() -> Result<GitStatus, String> {
+    let repo = Repository::open_from_env()
+        .map_err(OpenRepoError::from)
+        .map_err(|err| err.to_string())?;
+    GitStatus::from_repo(&repo).map_err(|err| err.to_string())
+}
 
 impl Runnable for Status {
     fn run(self) -> i32 {
-        let gs = match GitStatus::new() {
+        if self.exit_code {
+            return match repo_status() {
+                Ok(gs) => gs
+                    .iter()
+                    .any(|se| !matches!(se.state, EntryState::Ignored))
+                    .into(),
+                Err(_) => 2,
+            };
+        }
+        let gs = match repo_status() {
             Ok(status) => status,
             Err(err) => {
                 eprintln!("{}", err);
                 return 1;
             }
         };
-        match &gs.head {
-            WorktreeHead::Attached { head, upstream, .. } => {
-                println!("On branch {}", head.branch_name());
-                if let Some(upstream) = upstream {
-                    let msg = match (upstream.added, upstream.removed) {
-                        (0, 0) => format!("Your branch is up to date with '{}'.", upstream.name),
-                        (0, removed) => format!(
-                            "Your branch is behind '{}' by {} commit(s), and can be \
-                            fast-forwarded.",
-                            upstream.name, removed
-                        ),
-                        (added, 0) => format!(
-                            "Your branch is ahead of '{}' by {} commit(s).",
-                            upstream.name, added
-                        ),
-                        (added, removed) => format!(
-                            "Your branch and '{}' have diverged,\n\
-                        and have {} and {} different commits each, respectively.\n  \
-                        (use \"oaf merge {}\" to merge the remote branch into yours)",
-                            upstream.name, added, removed, upstream.name
-                        ),
-                    };
-                    println!("{}", msg);
-                }
+        if self.porcelain {
+            for se in gs.iter().fix_removals() {
+                println!("{}", se.format_porcelain());
             }
-            WorktreeHead::Detached(_) => {}
+            return 0;
+        }
+        if !self.no_branch {
+            print_branch_header(&gs.head);
+        }
+        if self.branch_only {
+            print_worktree_footer(&gs.head);
+            return 0;
         }
         let mut gs_iter = gs.iter();
         let cwd = env::current_dir().expect("Need cwd");
@@ -1247,11 +4029,18 @@ impl Runnable for Status {
             }
         };
         let top_rel = cwd.strip_prefix(top).unwrap();
-        for se in gs_iter.fix_removals() {
-            let out = se.format_entry(&top_rel);
-            println!("{}", out);
+        if self.grouped {
+            print_grouped(&gs_iter.fix_removals(), top_rel);
+        } else {
+            for se in gs_iter.fix_removals() {
+                let out = se.format_entry(&top_rel);
+                println!("{}", out);
+            }
         }
-        1
+        if !self.no_branch {
+            print_worktree_footer(&gs.head);
+        }
+        0
     }
 }
 
@@ -1259,7 +4048,12 @@ impl Runnable for Status {
 /**
 Tell git to ignore a file (that has not been added).
 
-This updates the top-level .gitignore, not any lower ones.
+This updates the top-level .gitignore, not any lower ones.  With --global, it updates your global
+excludes file ("core.excludesFile", or ~/.config/git/ignore), which applies to every repository.
+
+Files that have already been added are not affected by ignore patterns, so ignoring them prints a
+warning.  Use --untrack to stop tracking them, or --check to list tracked files that match ignore
+patterns.
 
 To ignore changes to files that have been added, see "ignore-changes".
 */
@@ -1267,13 +4061,36 @@ pub struct Ignore {
     /// Ignores the file in the local repository, instead of the worktree .gitignore.
     #[arg(long)]
     local: bool,
+    /// Ignore the patterns in every repository, using your global excludes file.  Patterns always
+    /// apply recursively.
+    #[arg(long, conflicts_with_all = ["local", "recurse", "untrack"])]
+    global: bool,
     /// Arguments should apply recursively.
     #[arg(long, short)]
     recurse: bool,
+    /// Also stop tracking the files, leaving them on disk.
+    #[arg(long)]
+    untrack: bool,
+    /// List tracked files that match ignore patterns, instead of ignoring files.
+    #[arg(long, conflicts_with_all = ["local", "global", "recurse", "untrack", "files"])]
+    check: bool,
     /// The list of files to ignore
     files: Vec<String>,
 }
 
+/// Warn about tracked files that match ignore patterns.  Returns true if there were any.
+fn warn_ignored_tracked(files: &[String]) -> bool {
+    if files.is_empty() {
+        return false;
+    }
+    eprintln!("These files match ignore patterns, but are still tracked:");
+    for file in files {
+        eprintln!("  {}", file);
+    }
+    eprintln!("Use \"oaf ignore --untrack\" to stop tracking them.");
+    true
+}
+
 /// Best-effort canonicalization.
 ///
 /// Canonicalizes the portions of the path that exist, ignores the rest.
@@ -1322,6 +4139,26 @@ fn add_ignores(entries: Vec<IgnoreEntry>, ignore_file: &Path) {
     .expect("Can't write .gitignore");
 }
 
+/// Find the global excludes file, creating its directory if needed.
+fn find_global_excludes() -> Result<PathBuf, String> {
+    let path = match run_config(&["--global", "--path", "--get", "core.excludesFile"]) {
+        Ok(output) => PathBuf::from(output_to_string(&output)),
+        Err(ConfigErr::SectionKeyInvalid) => {
+            let config_home = env::var_os("XDG_CONFIG_HOME")
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+                .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+                .ok_or("Cannot find the global excludes file, because HOME is not set.")?;
+            config_home.join("git").join("ignore")
+        }
+        Err(_) => return Err("Cannot read \"core.excludesFile\".".into()),
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| format!("{}: {}", parent.display(), err))?;
+    }
+    Ok(path)
+}
+
 impl Ignore {
     fn make_specific_entry(top: &Path, file: &str) -> IgnoreEntry {
         let path = normpath(&PathBuf::from(file)).unwrap();
@@ -1331,6 +4168,31 @@ impl Ignore {
 
 impl Runnable for Ignore {
     fn run(self) -> i32 {
+        if self.check {
+            return match list_ignored_tracked() {
+                Ok(files) => warn_ignored_tracked(&files) as i32,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    1
+                }
+            };
+        }
+        if self.global {
+            let ignore_file = match find_global_excludes() {
+                Ok(ignore_file) => ignore_file,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return 1;
+                }
+            };
+            let entries = self
+                .files
+                .iter()
+                .map(|line| IgnoreEntry::RecursiveEntry(PathBuf::from(line)))
+                .collect();
+            add_ignores(entries, &ignore_file);
+            return 0;
+        }
         let top = PathBuf::from(match get_toplevel() {
             Ok(top) => top,
             Err(err) => {
@@ -1359,6 +4221,22 @@ impl Runnable for Ignore {
             top.join(".gitignore")
         };
         add_ignores(entries, &ignore_file);
+        if !self.untrack {
+            // The warning is advisory, so failing to check does not fail the command.
+            if let Ok(files) = list_ignored_tracked() {
+                warn_ignored_tracked(&files);
+            }
+        }
+        if self.untrack {
+            let mut args = vec!["rm", "-r", "--cached", "--quiet", "--ignore-unmatch", "--"];
+            args.extend(self.files.iter().map(|s| s.as_str()));
+            let Ok(status) = make_git_command(&args).status() else {
+                return 1;
+            };
+            if !status.success() {
+                return status.code().unwrap_or(1);
+            }
+        }
         if !self.local {
             let mut cmd =
                 make_git_command(&[&OsString::from("add"), &ignore_file.as_os_str().to_owned()]);
@@ -1370,6 +4248,77 @@ impl Runnable for Ignore {
     }
 }
 
+#[derive(Debug, Args)]
+/**
+Stop ignoring files, by removing their entries from the top-level .gitignore.
+
+A file's entry is found whether it was written by "ignore" (e.g. "/foo") or written as given.
+*/
+pub struct Unignore {
+    /// Remove the entries from the local repository, instead of the worktree .gitignore.
+    #[arg(long)]
+    local: bool,
+    /// The files (or patterns) to stop ignoring.
+    #[arg(required = true)]
+    files: Vec<String>,
+}
+
+impl Runnable for Unignore {
+    fn run(self) -> i32 {
+        let top = PathBuf::from(match get_toplevel() {
+            Ok(top) => top,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        });
+        let top = top.canonicalize().unwrap();
+        let ignore_file = if self.local {
+            get_git_path("info/exclude")
+        } else {
+            top.join(".gitignore")
+        };
+        let ignores = match fs::read_to_string(&ignore_file) {
+            Ok(ignores) => ignores,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                eprintln!("{}: {}", ignore_file.display(), e);
+                return 1;
+            }
+        };
+        let mut lines: Vec<&str> = ignores.lines().collect();
+        let mut missing = vec![];
+        for file in &self.files {
+            let entry = Ignore::make_specific_entry(&top, file).make_string();
+            let count = lines.len();
+            lines.retain(|line| *line != entry && line != file);
+            if lines.len() == count {
+                missing.push(file);
+            }
+        }
+        for file in &missing {
+            eprintln!("{} is not ignored in {}", file, ignore_file.display());
+        }
+        if missing.len() == self.files.len() {
+            return 1;
+        }
+        let ignores: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+        if let Err(e) = fs::write(&ignore_file, ignores) {
+            eprintln!("{}: {}", ignore_file.display(), e);
+            return 1;
+        }
+        if !self.local {
+            let mut cmd =
+                make_git_command(&[&OsString::from("add"), &ignore_file.as_os_str().to_owned()]);
+            let Ok(status) = cmd.status() else { return 1 };
+            if !status.success() {
+                return status.code().unwrap_or(1);
+            }
+        }
+        (!missing.is_empty()).into()
+    }
+}
+
 #[derive(Debug, Args)]
 /// Ignore changes to a file.
 ///
@@ -1378,21 +4327,30 @@ impl Runnable for Ignore {
 ///
 /// If no files are supplied, list ignored files.
 ///
+/// By default, files are marked "assume-unchanged", which git may reset (e.g. when the file
+/// changes upstream).  To keep local edits to a tracked file, such as a config file, use
+/// --skip-worktree.
+///
 /// To ignore files that have not been added, see `ignore`.
 pub struct IgnoreChanges {
     files: Vec<String>,
     #[arg(long)]
     /// Stop ignoring (possible) changes to listed files
     unset: bool,
+    /// Mark the files "skip-worktree" instead of "assume-unchanged".  With --unset, clear
+    /// "skip-worktree" instead.
+    #[arg(long)]
+    skip_worktree: bool,
 }
 
 impl Runnable for IgnoreChanges {
     fn run(self) -> i32 {
         if !self.files.is_empty() {
-            let action = if self.unset {
-                "--no-assume-unchanged"
-            } else {
-                "--assume-unchanged"
+            let action = match (self.skip_worktree, self.unset) {
+                (false, false) => "--assume-unchanged",
+                (false, true) => "--no-assume-unchanged",
+                (true, false) => "--skip-worktree",
+                (true, true) => "--no-skip-worktree",
             };
             let mut args = vec!["update-index", action];
             args.extend(self.files.iter().map(|s| s.as_str()));
@@ -1401,7 +4359,11 @@ impl Runnable for IgnoreChanges {
             let output = run_git_command(&["ls-files", "-v"]).expect("Can't list files.");
             let mut matched = false;
             for line in output_to_string(&output).lines() {
-                if let Some(ignored_file) = line.strip_prefix("h ") {
+                // "h" is assume-unchanged, "S" is skip-worktree, and "s" is both.
+                if let Some(ignored_file) = ["h ", "S ", "s "]
+                    .iter()
+                    .find_map(|prefix| line.strip_prefix(prefix))
+                {
                     matched = true;
                     println!("{}", ignored_file);
                 }
@@ -1431,9 +4393,116 @@ impl<T: Display, U: RunOrError<Error = T>> Runnable for U {
     }
 }
 
+#[derive(Debug, Args)]
+/**
+List branches, or configure them.
+
+With --set-target, set the remembered target of several branches at once, as "merge --remember"
+does for the current branch.
+*/
+pub struct Branch {
+    /// Set the remembered target of the branches to this branch.
+    #[arg(long, value_name = "TARGET")]
+    set_target: Option<String>,
+    /// Configure all local branches (except the target itself).
+    #[arg(long, requires = "set_target", conflicts_with = "branches")]
+    all: bool,
+    /// The branches to configure.  Defaults to the current branch.
+    #[arg(requires = "set_target")]
+    branches: Vec<String>,
+    /// List only branches that contain this commit.
+    #[arg(long, value_name = "COMMIT", conflicts_with = "set_target")]
+    contains: Option<CommitSpec>,
+    /// List only branches that do not contain this commit.
+    #[arg(long, value_name = "COMMIT", conflicts_with = "set_target")]
+    no_contains: Option<CommitSpec>,
+}
+
+impl Runnable for Branch {
+    fn run(self) -> i32 {
+        let Some(target_name) = self.set_target else {
+            let mut args = vec!["branch".to_string()];
+            if let Some(commit) = self.contains {
+                args.extend(["--contains".to_string(), commit.spec]);
+            }
+            if let Some(commit) = self.no_contains {
+                args.extend(["--no-contains".to_string(), commit.spec]);
+            }
+            args.run_exit();
+        };
+        let Some(ExtantRefName {
+            name: Ok(target), ..
+        }) = ExtantRefName::resolve(&target_name)
+        else {
+            eprintln!("{} is not a branch.", target_name);
+            return 1;
+        };
+        let branches = if self.all {
+            match list_local_branches() {
+                Ok(branches) => branches
+                    .into_iter()
+                    .filter(|b| target.full() != b.full())
+                    .collect(),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return 1;
+                }
+            }
+        } else if self.branches.is_empty() {
+            match get_current_branch() {
+                Ok(branch) if !branch.branch_name().is_empty() => vec![branch],
+                _ => {
+                    eprintln!("No current branch.");
+                    return 1;
+                }
+            }
+        } else {
+            self.branches
+                .into_iter()
+                .map(LocalBranchName::from)
+                .collect()
+        };
+        for branch in &branches {
+            if branch.eval().is_err() {
+                eprintln!("Branch {} not found", branch.branch_name());
+                return 1;
+            }
+        }
+        let repo = match Repository::open_from_env().map_err(OpenRepoError::from) {
+            Ok(repo) => repo,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let target_name = target.find_shortest(&repo);
+        for branch in &branches {
+            if let Err(err) = set_target(branch, &target) {
+                eprintln!("Could not set target of {}: {}", branch.branch_name(), err);
+                return 1;
+            }
+            println!("Target of {} set to {}", branch.branch_name(), target_name);
+        }
+        detail(format!("{} is {}", target_name, target.full()));
+        0
+    }
+}
+
 #[derive(Debug, Args)]
 pub struct Revno {
     commit: Option<CommitSpec>,
+    /// Print the number of first-parent commits from COMMIT to END, instead.  COMMIT must be an
+    /// ancestor of END.
+    #[arg(value_name = "END", conflicts_with_all = ["short", "find"])]
+    end: Option<CommitSpec>,
+    /// Print "<branch>@r<revno>", which is unambiguous across branches.  When COMMIT is not a
+    /// branch, or HEAD is detached, print an abbreviated commit id instead.
+    #[arg(long)]
+    short: bool,
+    /// Print the id of the commit with revno N in the history of COMMIT, instead.  Revno 0 is the
+    /// empty tree that the first commit is based on.
+    #[arg(long, value_name = "N", conflicts_with = "short")]
+    find: Option<u32>,
 }
 
 impl RunOrError for Revno {
@@ -1444,14 +4513,86 @@ impl RunOrError for Revno {
             Some(spec) => spec,
             None => CommitSpec::from_str("HEAD")?,
         };
-        println!("{}", calc_revno(&repo, commit_spec.as_ref())?);
+        if let Some(revno) = self.find {
+            if revno == 0 {
+                println!("{}", EMPTY_TREE);
+                return Ok(0);
+            }
+            let Some(found) = i32::try_from(revno)
+                .ok()
+                .map(|revno| commit_from_revno(&repo, commit_spec.as_ref(), revno))
+                .transpose()?
+                .flatten()
+            else {
+                return Err(CommitErr::NoCommit {
+                    spec: format!("revno {}", revno),
+                });
+            };
+            println!("{}", found.sha);
+            return Ok(0);
+        }
+        let revno = calc_revno(&repo, commit_spec.as_ref())?;
+        if let Some(end) = self.end {
+            if run_git_command(&[
+                "merge-base",
+                "--is-ancestor",
+                &commit_spec.as_ref().sha,
+                &end.as_ref().sha,
+            ])
+            .is_err()
+            {
+                eprintln!("{} is not an ancestor of {}.", commit_spec.spec, end.spec);
+                return Ok(1);
+            }
+            println!("{}", calc_revno(&repo, end.as_ref())? - revno);
+            return Ok(0);
+        }
+        if !self.short {
+            println!("{}", revno);
+            return Ok(0);
+        }
+        match revno_label(&repo, &commit_spec.spec) {
+            Some(branch) => println!("{}@r{}", branch, revno),
+            None => println!("{}", &commit_spec.as_ref().sha[..7]),
+        }
         Ok(0)
     }
 }
 
+/// Find the name of the branch that spec refers to, for labelling its revno.  HEAD refers to the
+/// current branch, if any.
+fn revno_label(repo: &Repository, spec: &str) -> Option<String> {
+    if spec == "HEAD" {
+        return get_current_branch()
+            .ok()
+            .map(|branch| branch.branch_name().to_owned())
+            .filter(|name| !name.is_empty());
+    }
+    let branchy = BranchyName::UnresolvedName(spec.to_owned())
+        .resolve(repo)
+        .ok()?;
+    match BranchName::try_from(branchy).ok()? {
+        BranchName::Local(branch) => Some(branch.find_shortest(repo).into_owned()),
+        BranchName::Remote(branch) => Some(branch.find_shortest(repo).into_owned()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[test]
+    fn test_pipeline_entries_json() {
+        let branches = [
+            LocalBranchName::from("foo/bar".to_string()),
+            LocalBranchName::from("a\"b\\c\n\u{1}".to_string()),
+        ];
+        assert_eq!(
+            "[{\"name\":\"foo/bar\",\"current\":false},\
+             {\"name\":\"a\\\"b\\\\c\\n\\u0001\",\"current\":true}]",
+            serde_json::to_string(&pipeline_entries(&branches, Some(1))).unwrap()
+        );
+    }
+
     #[test]
     fn test_to_string() {
         assert_eq!(
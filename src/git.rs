@@ -16,6 +16,7 @@ use std::os::unix::ffi::OsStringExt;
 use std::path::PathBuf;
 use std::process::{Command, Output};
 use std::str::{from_utf8, FromStr};
+use std::sync::atomic::{AtomicU8, Ordering};
 
 #[derive(Debug)]
 pub enum OpenRepoError {
@@ -52,6 +53,52 @@ impl Display for OpenRepoError {
     }
 }
 
+impl Display for RefErr {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self {
+            RefErr::NotFound(err) | RefErr::Other(err) => err.fmt(formatter),
+            RefErr::NotBranch => write!(formatter, "Reference is not a branch."),
+            RefErr::NotUtf8 => write!(formatter, "Reference name is not valid UTF-8."),
+        }
+    }
+}
+
+/// How much informational output to produce.  Errors are always reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+pub fn verbosity() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Quiet,
+        1 => Verbosity::Normal,
+        _ => Verbosity::Verbose,
+    }
+}
+
+/// Print an informational message, unless quiet.
+pub fn inform(message: impl Display) {
+    if verbosity() >= Verbosity::Normal {
+        eprintln!("{}", message);
+    }
+}
+
+/// Print extra detail, only if verbose.
+pub fn detail(message: impl Display) {
+    if verbosity() >= Verbosity::Verbose {
+        eprintln!("{}", message);
+    }
+}
+
 pub fn run_git_command(args_vec: &[impl AsRef<OsStr>]) -> Result<Output, Output> {
     let process_output = make_git_command(args_vec)
         .output()
@@ -81,8 +128,10 @@ pub fn run_for_string(cmd: &mut Command) -> String {
 
 pub fn git_switch(
     target_branch: &str,
+    start_point: Option<&str>,
     create: bool,
     discard_changes: bool,
+    track: Option<bool>,
 ) -> Result<Output, GitError> {
     // Actual "switch" is not broadly deployed yet.
     // let mut switch_cmd = vec!["switch", "--discard-changes"];
@@ -92,12 +141,18 @@ pub fn git_switch(
         switch_cmd.push("--force");
     }
     if create {
-        if discard_changes && run_git_command(&["reset", "--hard"]).is_err() {
-            panic!("Failed to reset tree");
+        if discard_changes {
+            run_git_command(&["reset", "--hard"])?;
+        }
+        match track {
+            Some(true) => switch_cmd.push("--track"),
+            Some(false) => switch_cmd.push("--no-track"),
+            None => (),
         }
         switch_cmd.push("-b");
     }
     switch_cmd.push(target_branch);
+    switch_cmd.extend(start_point);
     switch_cmd.push("--");
     Ok(run_git_command(&switch_cmd)?)
 }
@@ -117,19 +172,46 @@ pub fn setting_exists(setting: &str) -> bool {
     }
 }
 
+/// Return the value of a setting, if it is set.
+pub fn get_setting(setting: &str) -> Option<String> {
+    match run_config(&["--get", setting]) {
+        Ok(output) => Some(output_to_string(&output)),
+        Err(ConfigErr::SectionKeyInvalid) => None,
+        Err(e) => panic!("{:?}", e),
+    }
+}
+
+/// Return the value of a boolean setting, or false if it is not set.
+pub fn get_bool_setting(setting: &str) -> bool {
+    match run_config(&["--type=bool", "--get", setting]) {
+        Ok(output) => output_to_string(&output) == "true",
+        Err(ConfigErr::SectionKeyInvalid) => false,
+        Err(e) => panic!("{:?}", e),
+    }
+}
+
 pub enum SettingLocation {
     Local,
+    Global,
+    System,
+}
+
+impl SettingLocation {
+    /// The `git config` option that selects this location.
+    fn config_option(&self) -> &'static str {
+        match self {
+            SettingLocation::Local => "--local",
+            SettingLocation::Global => "--global",
+            SettingLocation::System => "--system",
+        }
+    }
 }
 
 /**
  * Set a setting to a specific value.
  */
-pub fn set_setting(
-    _location: SettingLocation,
-    setting: &str,
-    value: &str,
-) -> Result<(), ConfigErr> {
-    run_config(&["--replace", "--local", setting, value])?;
+pub fn set_setting(location: SettingLocation, setting: &str, value: &str) -> Result<(), ConfigErr> {
+    run_config(&["--replace", location.config_option(), setting, value])?;
     Ok(())
 }
 
@@ -148,9 +230,15 @@ pub trait ReferenceSpec {
     fn find_shorthand(&self, repo: &Repository) -> Result<Option<String>, git2::Error> {
         Ok(self.find_reference(repo)?.shorthand().map(|s| s.to_owned()))
     }
+    /// Return the shorthand, unless it is ambiguous (e.g. a tag with the same name as a branch),
+    /// in which case return the full name.
     fn find_shortest(&self, repo: &Repository) -> Cow<str> {
         match self.find_shorthand(repo) {
-            Ok(Some(short_name)) => short_name.into(),
+            Ok(Some(short_name))
+                if resolve_refname(&short_name).is_some_and(|(full, _)| full == self.full()) =>
+            {
+                short_name.into()
+            }
             _ => self.full(),
         }
     }
@@ -559,7 +647,8 @@ pub fn upsert_ref(git_ref: &str, value: &str) -> Result<(), Output> {
 }
 
 pub fn delete_ref(git_ref: &str) -> Result<(), Output> {
-    run_git_command(&["update-ref", "-d", git_ref])?;
+    // Pipeline links are symbolic, and must be deleted rather than their targets.
+    run_git_command(&["update-ref", "-d", "--no-deref", git_ref])?;
     Ok(())
 }
 
@@ -567,12 +656,30 @@ pub fn set_head(new_head: &str) {
     run_git_command(&["reset", "--soft", new_head]).expect("Failed to update HEAD.");
 }
 
-pub fn create_stash() -> Option<String> {
-    let oid = run_for_string(&mut make_git_command(&["stash", "create"]));
-    if oid.is_empty() {
+/**
+ * Create a stash of the local changes, returning its oid, or None if there are no changes.
+ *
+ * Without include_untracked, the working tree is left unchanged.  With it, the stashed changes
+ * are removed from the working tree, because "stash create" cannot include untracked files, so
+ * "stash push" must be used.  The stash is then dropped from the stash list.
+ */
+pub fn create_stash(include_untracked: bool) -> Option<String> {
+    if !include_untracked {
+        let oid = run_for_string(&mut make_git_command(&["stash", "create"]));
+        if oid.is_empty() {
+            return None;
+        }
+        return Some(oid);
+    }
+    let old_oid = eval_rev_spec("refs/stash").ok();
+    run_git_command(&["stash", "push", "--include-untracked", "--quiet"])
+        .expect("Failed to stash changes.");
+    let oid = eval_rev_spec("refs/stash").ok();
+    if oid == old_oid {
         return None;
     }
-    Some(oid)
+    run_git_command(&["stash", "drop", "--quiet"]).expect("Failed to drop stash.");
+    oid
 }
 
 pub fn get_toplevel() -> Result<String, GitError> {
@@ -581,6 +688,66 @@ pub fn get_toplevel() -> Result<String, GitError> {
     ))
 }
 
+/**
+ * List tracked files that match an ignore pattern.  Tracked files are never actually ignored, so
+ * these are usually a mistake.  Paths are relative to the current directory.
+ */
+pub fn list_ignored_tracked() -> Result<Vec<String>, GitError> {
+    let output = run_git_command(&[
+        "ls-files",
+        "-z",
+        "--cached",
+        "--ignored",
+        "--exclude-standard",
+        "--",
+        ":/",
+    ])
+    .map_err(GitError::from)?;
+    Ok(output_to_string(&output)
+        .split_terminator('\0')
+        .map(|s| s.to_string())
+        .collect())
+}
+
+/// List all local branches.
+pub fn list_local_branches() -> Result<Vec<LocalBranchName>, GitError> {
+    let output = run_git_command(&["for-each-ref", "--format=%(refname)", "refs/heads/"])
+        .map_err(GitError::from)?;
+    Ok(output_to_string(&output)
+        .lines()
+        .filter_map(|line| LocalBranchName::from_long(line.to_string(), None).ok())
+        .collect())
+}
+
+/// Find the names checked out in the given reflog subjects, most recent first, without duplicates.
+fn parse_checkouts(reflog: &str) -> Vec<&str> {
+    let mut names = vec![];
+    for line in reflog.lines() {
+        let Some((_, to)) = line
+            .strip_prefix("checkout: moving from ")
+            .and_then(|moves| moves.rsplit_once(" to "))
+        else {
+            continue;
+        };
+        if !names.contains(&to) {
+            names.push(to);
+        }
+    }
+    names
+}
+
+/// List local branches in the order they were most recently checked out, according to HEAD's
+/// reflog.
+pub fn list_recent_branches() -> Result<Vec<LocalBranchName>, GitError> {
+    let output =
+        run_git_command(&["reflog", "show", "--format=%gs", "HEAD"]).map_err(GitError::from)?;
+    Ok(parse_checkouts(&output_to_string(&output))
+        .into_iter()
+        .map(|name| LocalBranchName::from(name.to_string()))
+        .filter(|branch| branch.eval().is_ok())
+        .collect())
+}
+
 fn one_liner(mut output: Output) -> OsString {
     output.stdout.pop();
     OsStringExt::from_vec(output.stdout)
@@ -598,6 +765,20 @@ pub fn get_git_path(sub_path: impl AsRef<OsStr>) -> PathBuf {
     PathBuf::from(&string)
 }
 
+/// Return the name of the operation (e.g. "merge") that is in progress, if any.
+pub fn find_operation_in_progress() -> Option<&'static str> {
+    [
+        ("MERGE_HEAD", "merge"),
+        ("rebase-merge", "rebase"),
+        ("rebase-apply", "rebase"),
+        ("CHERRY_PICK_HEAD", "cherry-pick"),
+        ("REVERT_HEAD", "revert"),
+    ]
+    .into_iter()
+    .find(|(path, _)| get_git_path(path).exists())
+    .map(|(_, operation)| operation)
+}
+
 /**
  * Escape characters that can appear in a git-compatible regex
  */
@@ -669,6 +850,26 @@ impl From<Output> for ConfigErr {
     }
 }
 
+impl Display for ConfigErr {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self {
+            ConfigErr::SectionKeyInvalid => write!(formatter, "Invalid setting name."),
+            ConfigErr::SectionKeyMissing => write!(formatter, "No setting name given."),
+            ConfigErr::ConfigInvalid => write!(formatter, "The config file is invalid."),
+            ConfigErr::ConfigUnwritable => write!(formatter, "Could not write the config file."),
+            ConfigErr::UnsetMissing => write!(formatter, "The setting is not set."),
+            ConfigErr::InvalidRegex => write!(formatter, "Invalid regular expression."),
+            ConfigErr::Other(output) => {
+                write!(
+                    formatter,
+                    "{}",
+                    String::from_utf8_lossy(&output.stderr).trim_end()
+                )
+            }
+        }
+    }
+}
+
 /**
  * Run 'git config' with supplied arguments
  */
@@ -812,6 +1013,19 @@ mod tests {
         );
     }
     #[test]
+    fn test_parse_checkouts() {
+        assert_eq!(
+            parse_checkouts(concat!(
+                "checkout: moving from feature to main\n",
+                "commit: Fix typo\n",
+                "checkout: moving from main to feature\n",
+                "checkout: moving from bugfix to main\n",
+                "reset: moving to HEAD~1\n",
+            )),
+            vec!["main", "feature"]
+        );
+    }
+    #[test]
     fn test_parse_branch_name() {
         let x = "refs/heads/foo".parse::<BranchName>();
         assert_eq!(
@@ -11,11 +11,11 @@ use std::env;
 use std::path::PathBuf;
 use std::process::exit;
 
-use commands::{NativeCommand, RunExit};
+use commands::{Opt, RunExit};
 use oaf::commands;
 
 fn is_oaf_cmd(args_vec: &[String]) -> bool {
-    let x = NativeCommand::try_parse_from(&args_vec[0..2]);
+    let x = Opt::try_parse_from(&args_vec[0..2]);
     if let Err(e) = x {
         if let clap::error::ErrorKind::UnknownArgument | clap::error::ErrorKind::InvalidSubcommand =
             e.kind()
@@ -37,9 +37,9 @@ fn extract_cmd(progname: &str) -> Option<&str> {
 /**
  * If the args are not an oaf command, but might be a git command, return None.
  *
- * Otherwise, return the result of parsing args as a NativeCommand.
+ * Otherwise, return the result of parsing args as an Opt.
  */
-fn parse_args(args_vec: &Vec<String>) -> Option<NativeCommand> {
+fn parse_args(args_vec: &Vec<String>) -> Option<Opt> {
     let mut args_iter = args_vec.clone().into_iter();
     let progpath = PathBuf::from(args_iter.next().expect("Invoked with 0 arguments"));
     let progname = progpath.file_name().unwrap().to_str().unwrap();
@@ -48,7 +48,7 @@ fn parse_args(args_vec: &Vec<String>) -> Option<NativeCommand> {
             if args_vec.len() > 1 && !is_oaf_cmd(args_vec) {
                 return None;
             }
-            NativeCommand::parse()
+            Opt::parse()
         }
         _ => {
             let Some(cmd) = extract_cmd(progname) else {
@@ -57,7 +57,7 @@ fn parse_args(args_vec: &Vec<String>) -> Option<NativeCommand> {
             };
             let mut args = vec!["oaf".to_string(), cmd.to_string()];
             args.extend(args_iter);
-            NativeCommand::parse_from(args)
+            Opt::parse_from(args)
         }
     };
     Some(opt)
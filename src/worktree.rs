@@ -7,13 +7,13 @@
 // except according to those terms.
 use super::branch::{check_link_branches, CheckedBranchLinks, LinkFailure};
 use super::git::{
-    create_stash, delete_ref, eval_rev_spec, get_toplevel, git_switch, make_git_command,
-    output_to_string, resolve_refname, run_git_command, set_head, set_setting, upsert_ref,
-    BranchName, BranchyName, ConfigErr, GitError, LocalBranchName, OpenRepoError, ReferenceSpec,
-    SettingLocation, SettingTarget, UnparsedReference,
+    create_stash, delete_ref, eval_rev_spec, find_operation_in_progress, get_toplevel, git_switch,
+    inform, make_git_command, output_to_string, resolve_refname, run_git_command, set_head,
+    set_setting, upsert_ref, BranchName, BranchyName, ConfigErr, GitError, LocalBranchName,
+    OpenRepoError, ReferenceSpec, SettingLocation, SettingTarget, UnparsedReference,
 };
 use enum_dispatch::enum_dispatch;
-use git2::Repository;
+use git2::{Repository, StatusOptions};
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt;
@@ -99,7 +99,8 @@ pub struct StatusEntry<'a> {
 }
 
 impl StatusEntry<'_> {
-    pub fn format_entry(&self, current_dir: &impl AsRef<Path>) -> String {
+    /// The characters describing the state of the entry in the index and on disk.
+    fn status_chars(&self) -> (&'static str, &'static str) {
         let track_char = match self.state {
             EntryState::Untracked => "?",
             EntryState::Ignored => "!",
@@ -136,6 +137,11 @@ impl StatusEntry<'_> {
                 UnmergedState::Deleted(Changer::Them) | UnmergedState::Added(Changer::Them) => " ",
             },
         };
+        (track_char, disk_char)
+    }
+
+    pub fn format_entry(&self, current_dir: &impl AsRef<Path>) -> String {
+        let (track_char, disk_char) = self.status_chars();
         let rename_str = if let EntryState::Renamed { old_filename, .. } = self.state {
             format!(
                 "{} -> ",
@@ -156,6 +162,18 @@ impl StatusEntry<'_> {
                 .to_string_lossy()
         )
     }
+
+    /// Format the entry as the status characters, a tab and the path relative to the top of the
+    /// tree.  Renamed entries are followed by another tab and the old path.
+    pub fn format_porcelain(&self) -> String {
+        let (track_char, disk_char) = self.status_chars();
+        let mut out = format!("{}{}\t{}", track_char, disk_char, self.filename);
+        if let EntryState::Renamed { old_filename, .. } = self.state {
+            out.push('\t');
+            out.push_str(old_filename);
+        }
+        out
+    }
 }
 
 pub struct StatusIter<'a> {
@@ -379,6 +397,24 @@ impl UpstreamInfo {
             removed,
         })
     }
+
+    /// Determine whether the upstream was rewritten (e.g. force-pushed) since the local branch was
+    /// based on it.
+    ///
+    /// The fork point is the most recent upstream commit (according to the upstream's reflog) that
+    /// the local branch contains.  If the upstream no longer contains it, its history was
+    /// rewritten.
+    pub fn is_rewritten(&self) -> bool {
+        if self.added == 0 || self.removed == 0 {
+            return false;
+        }
+        let Ok(output) = run_git_command(&["merge-base", "--fork-point", &self.name, "HEAD"])
+        else {
+            return false;
+        };
+        let fork_point = output_to_string(&output);
+        run_git_command(&["merge-base", "--is-ancestor", &fork_point, &self.name]).is_err()
+    }
 }
 
 pub fn make_worktree_head<'a>(mut raw_entries: impl Iterator<Item = &'a str>) -> WorktreeHead {
@@ -396,8 +432,13 @@ pub fn make_worktree_head<'a>(mut raw_entries: impl Iterator<Item = &'a str>) ->
             WorktreeHead::Detached(oid.to_string())
         } else {
             let upstream = UpstreamInfo::factory(raw_entries);
+            let commit = if oid == "(initial)" {
+                BranchCommit::Initial
+            } else {
+                BranchCommit::Oid(oid.to_string())
+            };
             WorktreeHead::Attached {
-                commit: BranchCommit::Oid(oid.to_string()),
+                commit,
                 head: LocalBranchName::from(head.to_string()),
                 upstream,
             }
@@ -406,15 +447,52 @@ pub fn make_worktree_head<'a>(mut raw_entries: impl Iterator<Item = &'a str>) ->
         WorktreeHead::Attached {
             commit: BranchCommit::Initial,
             head: LocalBranchName::from("".to_string()),
-            upstream: Some(UpstreamInfo {
-                name: "".to_string(),
-                added: 0,
-                removed: 0,
-            }),
+            upstream: None,
         }
     }
 }
 
+/// Render the branch headers of `git status --porcelain=v2 -z --branch` for the repository.
+fn status_header(repo: &Repository) -> Result<String, git2::Error> {
+    let head = match repo.head() {
+        Ok(head) => head,
+        Err(err) if err.code() == git2::ErrorCode::UnbornBranch => {
+            let head = repo.find_reference("HEAD")?;
+            let name = head
+                .symbolic_target()
+                .and_then(|t| t.strip_prefix("refs/heads/"))
+                .unwrap_or_default();
+            return Ok(format!("# branch.oid (initial)\0# branch.head {}\0", name));
+        }
+        Err(err) => return Err(err),
+    };
+    let oid = head.peel_to_commit()?.id();
+    if repo.head_detached()? {
+        return Ok(format!("# branch.oid {}\0# branch.head (detached)\0", oid));
+    }
+    let mut header = format!(
+        "# branch.oid {}\0# branch.head {}\0",
+        oid,
+        head.shorthand().unwrap_or_default()
+    );
+    let Some(upstream) = head
+        .name()
+        .and_then(|name| repo.branch_upstream_name(name).ok())
+        .and_then(|name| name.as_str().map(|n| n.to_owned()))
+        .and_then(|name| repo.find_reference(&name).ok())
+    else {
+        return Ok(header);
+    };
+    let (ahead, behind) = repo.graph_ahead_behind(oid, upstream.peel_to_commit()?.id())?;
+    header.push_str(&format!(
+        "# branch.upstream {}\0# branch.ab +{} -{}\0",
+        upstream.shorthand().unwrap_or_default(),
+        ahead,
+        behind
+    ));
+    Ok(header)
+}
+
 /// Represents `git status` output
 #[derive(Debug)]
 pub struct GitStatus {
@@ -451,6 +529,132 @@ impl GitStatus {
         Ok(result)
     }
 
+    /**
+    Return a [GitStatus] for the repository, without running a git subprocess.
+
+    The status is rendered in the format of `git status --porcelain=v2 -z --branch`, so it is
+    parsed exactly as git's output is.  Object ids and modes are not reported.
+    */
+    pub fn from_repo(repo: &Repository) -> Result<GitStatus, git2::Error> {
+        let mut outstr = status_header(repo)?;
+        let mut conflicts = HashMap::new();
+        for conflict in repo.index()?.conflicts()? {
+            let conflict = conflict?;
+            let Some(entry) = conflict
+                .our
+                .as_ref()
+                .or(conflict.their.as_ref())
+                .or(conflict.ancestor.as_ref())
+            else {
+                continue;
+            };
+            let code = match (
+                conflict.ancestor.is_some(),
+                conflict.our.is_some(),
+                conflict.their.is_some(),
+            ) {
+                (true, false, false) => "DD",
+                (false, true, false) => "AU",
+                (true, true, false) => "UD",
+                (false, false, true) => "UA",
+                (true, false, true) => "DU",
+                (false, true, true) => "AA",
+                _ => "UU",
+            };
+            conflicts.insert(String::from_utf8_lossy(&entry.path).into_owned(), code);
+        }
+        let untracked_files = repo
+            .config()?
+            .get_string("status.showUntrackedFiles")
+            .unwrap_or_default();
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(untracked_files != "no")
+            .recurse_untracked_dirs(untracked_files == "all")
+            .renames_head_to_index(true)
+            .renames_index_to_workdir(true)
+            .exclude_submodules(true);
+        // git lists unmerged entries after the other tracked entries.
+        let mut unmerged = String::new();
+        let mut untracked = String::new();
+        for entry in repo.statuses(Some(&mut options))?.iter() {
+            let status = entry.status();
+            let Some(path) = entry.path() else {
+                continue;
+            };
+            let no_ids = format!("N... 000000 000000 000000 {0:040} {0:040}", 0);
+            if status.is_conflicted() {
+                let code = conflicts.get(path).unwrap_or(&"UU");
+                unmerged.push_str(&format!(
+                    "u {} N... 000000 000000 000000 000000 {1:040} {1:040} {1:040} {2}\0",
+                    code, 0, path
+                ));
+                continue;
+            }
+            if status.is_ignored() {
+                untracked.push_str(&format!("! {}\0", path));
+                continue;
+            }
+            let index_code = if status.is_index_new() {
+                "A"
+            } else if status.is_index_deleted() {
+                "D"
+            } else if status.is_index_renamed() {
+                "R"
+            } else if status.is_index_modified() || status.is_index_typechange() {
+                "M"
+            } else {
+                "."
+            };
+            if status.is_wt_new() {
+                // A file removed from the index but not from disk is also untracked.
+                untracked.push_str(&format!("? {}\0", path));
+                if index_code == "." {
+                    continue;
+                }
+            }
+            // libgit2 pairs a deleted file with an untracked one, but git only detects renames in
+            // the working tree for intent-to-add files, so report a deletion and an untracked file.
+            if status.is_wt_renamed() {
+                if let Some(new_path) = entry
+                    .index_to_workdir()
+                    .and_then(|delta| delta.new_file().path())
+                {
+                    untracked.push_str(&format!("? {}\0", new_path.to_string_lossy()));
+                }
+            }
+            let tree_code = if status.is_wt_deleted() || status.is_wt_renamed() {
+                "D"
+            } else if status.is_wt_modified() || status.is_wt_typechange() {
+                "M"
+            } else {
+                "."
+            };
+            let rename = entry
+                .head_to_index()
+                .filter(|_| status.is_index_renamed())
+                .and_then(|delta| Some((delta.old_file().path()?, delta.new_file().path()?)));
+            match rename {
+                Some((old_path, new_path)) => outstr.push_str(&format!(
+                    "2 {}{} {} R100 {}\0{}\0",
+                    index_code,
+                    tree_code,
+                    no_ids,
+                    new_path.to_string_lossy(),
+                    old_path.to_string_lossy()
+                )),
+                None => outstr.push_str(&format!(
+                    "1 {}{} {} {}\0",
+                    index_code, tree_code, no_ids, path
+                )),
+            }
+        }
+        outstr.push_str(&unmerged);
+        outstr.push_str(&untracked);
+        let head = make_worktree_head(outstr.split_terminator('\0'));
+        Ok(GitStatus { outstr, head })
+    }
+
     /** List untracked filenames
 
     This is a convenience wrapper for callers that just want to fail on untracked files.
@@ -461,6 +665,13 @@ impl GitStatus {
             .map(|es| es.filename.to_string())
             .collect()
     }
+
+    pub fn unmerged_filenames(&self) -> Vec<String> {
+        self.iter()
+            .filter(|f| matches!(f.state, EntryState::Unmerged { .. }))
+            .map(|es| es.filename.to_string())
+            .collect()
+    }
 }
 
 /// Refers to a tree object specifically, not a commit
@@ -771,7 +982,9 @@ pub fn calc_revno(repo: &Repository, commit: &Commit) -> Result<i32, git2::Error
     Ok((walker.count()).try_into().unwrap())
 }
 
-fn commit_from_revno(
+/// Find the commit with the given revno in the first-parent history of `tip`.  Negative revnos
+/// count back from `tip`, which is -1.  None is returned for 0 and for revnos out of range.
+pub fn commit_from_revno(
     repo: &Repository,
     tip: &Commit,
     revno: i32,
@@ -832,10 +1045,13 @@ impl FromStr for Commit {
     }
 }
 
+/// The id of the tree with no entries, which git knows about even if it is not stored.
+pub const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
 pub fn base_tree() -> Result<TreeSpec, GitError> {
     let reference = match Commit::from_str("HEAD") {
         Ok(commit) => commit.get_tree_reference().into(),
-        Err(CommitErr::NoCommit { .. }) => "4b825dc642cb6eb9a060e54bf8d69288fbee4904".into(),
+        Err(CommitErr::NoCommit { .. }) => EMPTY_TREE.into(),
         Err(CommitErr::GitError(err)) => return Err(err),
     };
     Ok(TreeSpec { reference })
@@ -903,9 +1119,9 @@ pub fn list_worktree() -> Vec<WorktreeListEntry> {
     parse_worktree_list(&output_to_string(&output))
 }
 
-pub fn create_wip_stash(current: &BranchOrCommit) -> Option<WipReference> {
+pub fn create_wip_stash(current: &BranchOrCommit, include_untracked: bool) -> Option<WipReference> {
     let current_ref = WipReference::from(current);
-    match create_stash() {
+    match create_stash(include_untracked) {
         Some(oid) => {
             if upsert_ref(&current_ref.full(), &oid).is_err() {
                 panic!("Failed to set reference {} to {}", current_ref.full(), oid);
@@ -921,14 +1137,21 @@ pub fn create_wip_stash(current: &BranchOrCommit) -> Option<WipReference> {
     }
 }
 
-pub fn apply_wip_stash(target: &BranchOrCommit) -> bool {
+pub fn apply_wip_stash(target: &BranchOrCommit, keep_stash: bool) -> bool {
+    try_apply_wip_stash(target, keep_stash).unwrap()
+}
+
+/// Apply the target's WIP changes, if any.  If applying fails, the WIP reference is kept.
+pub fn try_apply_wip_stash(target: &BranchOrCommit, keep_stash: bool) -> Result<bool, Output> {
     let target_ref = WipReference::from(target);
     let Ok(target_oid) = target_ref.eval() else {
-        return false;
+        return Ok(false);
     };
-    run_git_command(&["stash", "apply", &target_oid]).unwrap();
-    target_ref.delete().unwrap();
-    true
+    run_git_command(&["stash", "apply", &target_oid])?;
+    if !keep_stash {
+        target_ref.delete().unwrap();
+    }
+    Ok(true)
 }
 
 pub fn make_wip_ref(current: &BranchOrCommit) -> String {
@@ -995,6 +1218,24 @@ pub enum SwitchErr {
     GitError(GitError),
     OpenRepoError(OpenRepoError),
     LinkFailure(String),
+    OperationInProgress(&'static str),
+    LocalChangesWouldBeOverwritten { files: Vec<String> },
+    UnresolvedConflicts { files: Vec<String> },
+}
+
+/// Parse the files from git's "local changes ... would be overwritten" error, if that is the
+/// error.
+fn parse_overwritten_files(stderr: &str) -> Option<Vec<String>> {
+    let mut lines = stderr.lines();
+    lines.find(|line| {
+        line.starts_with("error: Your local changes to the following files would be overwritten")
+    })?;
+    Some(
+        lines
+            .map_while(|line| line.strip_prefix('\t'))
+            .map(|file| file.to_owned())
+            .collect(),
+    )
 }
 
 impl From<LinkFailure<'_>> for SwitchErr {
@@ -1079,6 +1320,9 @@ pub fn target_branch_setting(
 #[derive(Clone, PartialEq, Eq)]
 pub enum SwitchType {
     Create(LocalBranchName),
+    /// Create a branch at the specified start point.  Unlike Create, local changes are stashed
+    /// with the current branch, because they may not apply at the start point.
+    CreateFrom(LocalBranchName, String),
     CreateNext(LocalBranchName),
     WithStash(BranchyName),
     PlainSwitch(BranchyName),
@@ -1090,12 +1334,44 @@ impl From<GitError> for SwitchErr {
     }
 }
 
-pub fn stash_switch(switch_type: SwitchType) -> Result<(), SwitchErr> {
+/// Options that modify how [stash_switch] behaves.
+#[derive(Debug, Default, Clone)]
+pub struct SwitchOptions {
+    /// Leave the target's WIP reference in place after applying it.  It is replaced the next time
+    /// the branch is switched away from.
+    pub keep_stash: bool,
+    /// Whether a newly-created branch tracks its start point.  If None, git's
+    /// "branch.autoSetupMerge" setting decides.
+    pub track: Option<bool>,
+    /// Include untracked files in the stash, removing them from the working tree.
+    pub include_untracked: bool,
+    /// Switch even if there are unresolved conflicts, treating the conflicted files as ordinary
+    /// local changes.
+    pub force: bool,
+}
+
+pub fn stash_switch(switch_type: SwitchType, options: &SwitchOptions) -> Result<(), SwitchErr> {
     use SwitchType::*;
     let top: String = get_toplevel()?;
+    if let Some(operation) = find_operation_in_progress() {
+        return Err(SwitchErr::OperationInProgress(operation));
+    }
+    // Neither stashing nor switching works with unmerged entries, so report them up front.
+    let files = GitStatus::new()?.unmerged_filenames();
+    if !files.is_empty() {
+        if !options.force {
+            return Err(SwitchErr::UnresolvedConflicts { files });
+        }
+        // Unstaging the conflicts keeps their contents, markers included, as local changes.
+        let mut reset_cmd = vec!["reset", "--quiet", "--"];
+        reset_cmd.extend(files.iter().map(|file| file.as_str()));
+        run_git_command(&reset_cmd).map_err(GitError::from)?;
+    }
     let current = {
         let target = match switch_type.clone() {
-            Create(target) | CreateNext(target) => Some(check_create_target(target)?),
+            Create(target) | CreateFrom(target, _) | CreateNext(target) => {
+                Some(check_create_target(target)?)
+            }
             PlainSwitch(target) | WithStash(target) => {
                 if let BranchyName::LocalBranch(target) = target {
                     Some(target)
@@ -1123,46 +1399,97 @@ pub fn stash_switch(switch_type: SwitchType) -> Result<(), SwitchErr> {
             )?);
         }
     }
+    // Switching to the current branch does nothing, unless there is stranded WIP to restore.
+    if let (PlainSwitch(target) | WithStash(target), BranchOrCommit::Branch(current_branch)) =
+        (&switch_type, &current)
+    {
+        if target.get_as_branch() == current_branch.branch_name()
+            && !(matches!(switch_type, WithStash(_)) && WipReference::from(&current).eval().is_ok())
+        {
+            inform(format!("Already on {}", current_branch.branch_name()));
+            return Ok(());
+        }
+    }
     let mut new_stash = None;
-    if matches!(switch_type, WithStash(_)) {
-        new_stash = create_wip_stash(&current);
+    // Switching to the current branch restores any WIP stranded by an interrupted switch, so it
+    // must not replace that WIP with a new stash.
+    let reswitch = match (&switch_type, &current) {
+        (WithStash(target), BranchOrCommit::Branch(current)) => {
+            target.get_as_branch() == current.branch_name()
+        }
+        _ => false,
+    };
+    // Creating a branch at a new start point stashes, because the local changes may not apply
+    // there.
+    let stash = matches!(switch_type, WithStash(_) | CreateFrom(..)) && !reswitch;
+    if stash {
+        new_stash = create_wip_stash(&current, options.include_untracked);
         if let Some(current_ref) = &new_stash {
-            eprintln!("Stashed WIP changes to {}", current_ref.full());
+            inform(format!("Stashed WIP changes to {}", current_ref.full()));
         } else {
-            eprintln!("No changes to stash");
+            inform("No changes to stash");
         }
-    } else {
-        eprintln!("Retaining any local changes.");
+    } else if !reswitch {
+        inform("Retaining any local changes.");
     }
-    let create = matches!(switch_type, Create(_) | CreateNext(_));
+    let create = matches!(switch_type, Create(_) | CreateFrom(..) | CreateNext(_));
     let branchy = match switch_type.clone() {
-        Create(target) | CreateNext(target) => target.branch_name().to_owned(),
+        Create(target) | CreateFrom(target, _) | CreateNext(target) => {
+            target.branch_name().to_owned()
+        }
         PlainSwitch(target) | WithStash(target) => target.get_as_branch().to_string(),
     };
-    if let Err(e) = git_switch(&branchy, create, !create) {
-        if let GitError::UnknownError(stderr) = e {
-            if stderr
-                .to_string_lossy()
-                .starts_with("fatal: invalid reference")
-            {
-                if let Some(current_ref) = &new_stash {
-                    current_ref
-                        .delete()
-                        .expect("Failed to delete reference to new stash.");
+    let start_point = match &switch_type {
+        CreateFrom(_, start_point) => Some(start_point.as_str()),
+        _ => None,
+    };
+    // Only a stashing switch discards local changes; otherwise, git refuses to overwrite them.
+    if let Err(e) = git_switch(&branchy, start_point, create, stash, options.track) {
+        let err = match e {
+            GitError::UnknownError(stderr) => {
+                let stderr = stderr.to_string_lossy();
+                if stderr.starts_with("fatal: invalid reference") {
+                    SwitchErr::NotFound
+                } else if let Some(files) = parse_overwritten_files(&stderr) {
+                    SwitchErr::LocalChangesWouldBeOverwritten { files }
+                } else {
+                    SwitchErr::GitError(GitError::UnknownError(stderr.into_owned().into()))
                 }
-                return Err(SwitchErr::NotFound);
+            }
+            e => SwitchErr::GitError(e),
+        };
+        if let Some(current_ref) = &new_stash {
+            // Stashing untracked files removed the changes, and so did resetting the tree to
+            // create a branch, so they must be restored.
+            if options.include_untracked || create {
+                if try_apply_wip_stash(&current, false).is_err() {
+                    eprintln!(
+                        "Could not restore WIP changes.  They are stashed in {}.",
+                        current_ref.full()
+                    );
+                }
+            } else {
+                current_ref
+                    .delete()
+                    .expect("Failed to delete reference to new stash.");
             }
         }
-        panic!("Failed to switch to {}", branchy);
+        return Err(err);
     }
-    eprintln!("Switched to {}", branchy);
+    inform(format!("Switched to {}", branchy));
     if let WithStash(target) = &switch_type {
         match determine_switch_target(&repo, target.clone()) {
             Ok(target_bc) => {
-                if apply_wip_stash(&target_bc) {
-                    eprintln!("Applied WIP changes for {}", target.get_as_branch());
+                if apply_wip_stash(&target_bc, options.keep_stash) {
+                    inform(format!(
+                        "Applied WIP changes for {}",
+                        target.get_as_branch()
+                    ));
                 } else {
-                    eprintln!("No WIP changes for {} to restore", target.get_as_branch());
+                    inform(format!(
+                        "No WIP changes for {} to restore",
+                        target.get_as_branch()
+                    ));
                 }
             }
             // Assume this is a remote branch being referred to as a local branch's name, i.e. a
@@ -1173,8 +1500,13 @@ pub fn stash_switch(switch_type: SwitchType) -> Result<(), SwitchErr> {
             }
         }
     }
+    if let CreateFrom(target, _) = &switch_type {
+        if apply_wip_stash(&BranchOrCommit::Branch(target.clone()), options.keep_stash) {
+            inform(format!("Applied WIP changes for {}", target.branch_name()));
+        }
+    }
     match &switch_type {
-        Create(target) | CreateNext(target) => {
+        Create(target) | CreateFrom(target, _) | CreateNext(target) => {
             if let BranchOrCommit::Branch(old_branch) = current {
                 set_target(target, &BranchName::Local(old_branch))
                     .expect("Could not set target branch.");
@@ -1278,6 +1610,46 @@ mod tests {
             }
         )
     }
+    #[test]
+    fn test_format_porcelain() {
+        let entry = StatusEntry {
+            state: EntryState::Changed {
+                staged_status: EntryLocationStatus::Added,
+                tree_status: EntryLocationStatus::Unmodified,
+            },
+            filename: "foo/bar",
+        };
+        assert_eq!("+A\tfoo/bar", entry.format_porcelain());
+        let entry = StatusEntry {
+            state: EntryState::Renamed {
+                staged_status: EntryLocationStatus::Renamed,
+                tree_status: EntryLocationStatus::Modified,
+                old_filename: "baz",
+            },
+            filename: "foo/bar",
+        };
+        assert_eq!("RM\tfoo/bar\tbaz", entry.format_porcelain());
+    }
+
+    #[test]
+    fn test_parse_overwritten_files() {
+        assert_eq!(
+            Some(vec!["foo".to_string(), "bar/baz".to_string()]),
+            parse_overwritten_files(concat!(
+                "error: Your local changes to the following files would be overwritten by ",
+                "checkout:\n",
+                "\tfoo\n",
+                "\tbar/baz\n",
+                "Please commit your changes or stash them before you switch branches.\n",
+                "Aborting\n",
+            ))
+        );
+        assert_eq!(
+            None,
+            parse_overwritten_files("fatal: invalid reference: foo\n")
+        );
+    }
+
     #[test]
     fn test_join_lines() {
         let lines = vec!["hello".to_string(), "there".to_string()];
@@ -1401,6 +1773,22 @@ mod tests {
         );
     }
     #[test]
+    fn test_make_worktree_head_initial() {
+        let info = make_worktree_head(
+            ["# branch.oid (initial)", "# branch.head main"]
+                .iter()
+                .map(|x| *x),
+        );
+        assert_eq!(
+            info,
+            WorktreeHead::Attached {
+                commit: BranchCommit::Initial,
+                head: LocalBranchName::from("main".to_string()),
+                upstream: None,
+            }
+        );
+    }
+    #[test]
     fn test_make_worktree_head_attached_more() {
         let info = make_worktree_head(
             ["# branch.oid hello", "# branch.head main", "asdf"]
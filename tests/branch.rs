@@ -0,0 +1,192 @@
+use git2::Repository;
+
+use oaf::git::{make_git_command, LocalBranchName, ReferenceSpec};
+mod common;
+use common::{git, oaf, ref_exists, run_oaf, RunFallible};
+
+#[test]
+fn ambiguous_with_tag() {
+    let work_dir = common::init_repo();
+    let repo = Repository::open(&work_dir).unwrap();
+    make_git_command(&["branch", "foo"]).run_check();
+    let foo = LocalBranchName::from("foo".to_string());
+    assert_eq!(foo.find_shortest(&repo), "foo");
+    make_git_command(&["tag", "foo"]).run_check();
+    assert_eq!(foo.find_shortest(&repo), "refs/heads/foo");
+}
+
+#[test]
+fn branch_contains() {
+    let work_dir = common::init_repo_no_chdir();
+    git(&work_dir, &["branch", "old"]);
+    git(&work_dir, &["commit", "--allow-empty", "-m", "fix"]);
+    assert_eq!("* main", oaf(&work_dir, &["branch", "--contains", "HEAD"]));
+    assert_eq!("old", oaf(&work_dir, &["branch", "--no-contains", "HEAD"]));
+}
+
+#[test]
+fn tag_list_filters() {
+    let work_dir = common::init_repo_no_chdir();
+    for tag in ["v1.2", "v1.9"] {
+        git(&work_dir, &["tag", tag]);
+    }
+    git(&work_dir, &["commit", "-q", "--allow-empty", "-m", "fix"]);
+    git(&work_dir, &["tag", "v1.10"]);
+    assert_eq!("v1.2\nv1.9\nv1.10", oaf(&work_dir, &["tag"]));
+    assert_eq!("v1.10", oaf(&work_dir, &["tag", "--contains", "HEAD"]));
+    assert_eq!(
+        "v1.2\nv1.9",
+        oaf(&work_dir, &["tag", "--no-contains", "HEAD"])
+    );
+    assert_eq!("v1.2\nv1.9", oaf(&work_dir, &["tag", "--merged", "HEAD~1"]));
+    assert_eq!("v1.10", oaf(&work_dir, &["tag", "--no-merged", "HEAD~1"]));
+}
+
+#[test]
+fn delete_unmerged_branch() {
+    let work_dir = common::init_repo_no_chdir();
+    oaf(&work_dir, &["switch-next", "-c", "feature"]);
+    git(
+        &work_dir,
+        &["commit", "--allow-empty", "-m", "feature work"],
+    );
+    oaf(&work_dir, &["switch", "main"]);
+    assert!(!run_oaf(&work_dir, &["delete-branch", "feature"])
+        .status
+        .success());
+    assert!(ref_exists(&work_dir, "refs/heads/feature"));
+    oaf(&work_dir, &["delete-branch", "--force", "feature"]);
+    assert!(!ref_exists(&work_dir, "refs/heads/feature"));
+    assert!(!ref_exists(&work_dir, "refs/pipe-next/main"));
+    let setting = make_git_command(&["config", "--get", "branch.feature.oaf-target-branch"])
+        .current_dir(&work_dir)
+        .status()
+        .unwrap();
+    assert!(!setting.success());
+}
+
+#[test]
+fn rename_branch_old_is_optional() {
+    let work_dir = common::init_repo_no_chdir();
+    oaf(&work_dir, &["rename-branch", "renamed"]);
+    assert_eq!("renamed", git(&work_dir, &["branch", "--show-current"]));
+    git(&work_dir, &["branch", "other"]);
+    oaf(&work_dir, &["rename-branch", "other", "other2"]);
+    assert_eq!(
+        "other2\nrenamed",
+        git(&work_dir, &["branch", "--format=%(refname:short)"])
+    );
+}
+
+#[test]
+fn rename_branch_full_old_name() {
+    let work_dir = common::init_repo_no_chdir();
+    git(&work_dir, &["branch", "other"]);
+    oaf(&work_dir, &["branch", "--set-target", "main", "other"]);
+    oaf(&work_dir, &["rename-branch", "refs/heads/other", "other2"]);
+    assert!(!ref_exists(&work_dir, "refs/heads/other"));
+    assert!(ref_exists(&work_dir, "refs/heads/other2"));
+    assert_eq!(
+        "refs/heads/main",
+        git(&work_dir, &["config", "branch.other2.oaf-target-branch"])
+    );
+}
+
+#[test]
+fn prune_merged() {
+    let work_dir = common::init_repo_no_chdir();
+    git(&work_dir, &["branch", "landed"]);
+    git(&work_dir, &["switch", "-q", "-c", "unlanded"]);
+    git(
+        &work_dir,
+        &["commit", "--allow-empty", "-m", "feature work"],
+    );
+    git(&work_dir, &["switch", "-q", "main"]);
+    oaf(
+        &work_dir,
+        &["branch", "--set-target", "main", "landed", "unlanded"],
+    );
+    assert_eq!(
+        "landed (merged into main)",
+        oaf(&work_dir, &["prune-merged"])
+    );
+    assert!(ref_exists(&work_dir, "refs/heads/landed"));
+    oaf(&work_dir, &["prune-merged", "--delete"]);
+    assert!(!ref_exists(&work_dir, "refs/heads/landed"));
+    assert!(ref_exists(&work_dir, "refs/heads/unlanded"));
+    let setting = make_git_command(&["config", "--get", "branch.landed.oaf-target-branch"])
+        .current_dir(&work_dir)
+        .status()
+        .unwrap();
+    assert!(!setting.success());
+}
+
+#[test]
+fn prune_wip() {
+    let work_dir = common::init_repo_no_chdir();
+    oaf(&work_dir, &["switch-next", "-c", "gone"]);
+    git(&work_dir, &["update-ref", "refs/branch-wip/gone", "HEAD"]);
+    git(&work_dir, &["switch", "-q", "main"]);
+    git(&work_dir, &["branch", "-q", "-D", "gone"]);
+    assert_eq!(
+        "Would delete refs/branch-wip/gone\nWould delete refs/pipe-next/main\n\
+        Would delete refs/pipe-prev/gone",
+        oaf(&work_dir, &["prune-wip", "--dry-run"])
+    );
+    oaf(&work_dir, &["prune-wip"]);
+    let refs = git(
+        &work_dir,
+        &[
+            "for-each-ref",
+            "--format=%(refname)",
+            "refs/branch-wip/",
+            "refs/pipe-next/",
+            "refs/pipe-prev/",
+        ],
+    );
+    assert_eq!("", refs);
+    assert!(ref_exists(&work_dir, "refs/heads/main"));
+}
+
+#[test]
+fn init_targets() {
+    let work_dir = common::init_repo_no_chdir();
+    let get_target = |branch: &str| {
+        git(
+            &work_dir,
+            &[
+                "config",
+                "--default=",
+                "--get",
+                &format!("branch.{}.oaf-target-branch", branch),
+            ],
+        )
+    };
+    git(&work_dir, &["branch", "feature"]);
+    git(&work_dir, &["branch", "--track", "follower", "feature"]);
+    oaf(&work_dir, &["init-target", "--dry-run"]);
+    assert_eq!("", get_target("feature"));
+    oaf(&work_dir, &["init-target"]);
+    assert_eq!("", get_target("main"));
+    assert_eq!("refs/heads/main", get_target("feature"));
+    assert_eq!("refs/heads/feature", get_target("follower"));
+}
+
+#[test]
+fn set_target_reports_short_name() {
+    let work_dir = common::init_repo_no_chdir();
+    git(&work_dir, &["branch", "feature"]);
+    assert_eq!(
+        "Target of feature set to main",
+        oaf(&work_dir, &["branch", "--set-target", "main", "feature"])
+    );
+    let output = run_oaf(
+        &work_dir,
+        &["-v", "branch", "--set-target", "main", "feature"],
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("main is refs/heads/main\n"));
+    assert_eq!(
+        "refs/heads/main",
+        git(&work_dir, &["config", "branch.feature.oaf-target-branch"])
+    );
+}
@@ -0,0 +1,144 @@
+use std::fs;
+
+mod common;
+use common::{git, oaf, oaf_command, run_oaf};
+
+fn commit_succeeds(work_dir: &tempfile::TempDir, args: &[&str]) -> bool {
+    oaf_command(work_dir)
+        .arg("commit")
+        .args(args)
+        .status()
+        .unwrap()
+        .success()
+}
+
+#[test]
+fn amend_keeps_author_date() {
+    let work_dir = common::init_repo_no_chdir();
+    git(
+        &work_dir,
+        &[
+            "commit",
+            "--amend",
+            "--no-edit",
+            "--date=2000-01-01T00:00:00+00:00",
+        ],
+    );
+    let author_date = || {
+        git(
+            &work_dir,
+            &["log", "-1", "--format=%ad", "--date=iso-strict"],
+        )
+    };
+    oaf(&work_dir, &["commit", "--amend", "-m", "amended"]);
+    assert_eq!("2000-01-01T00:00:00+00:00", author_date());
+    oaf(
+        &work_dir,
+        &["commit", "--amend", "-m", "amended", "--date=now"],
+    );
+    assert!(!author_date().starts_with("2000-"));
+}
+
+#[test]
+fn amend_reports_commit() {
+    let work_dir = common::init_repo_no_chdir();
+    let output = run_oaf(
+        &work_dir,
+        &[
+            "commit",
+            "--amend",
+            "-m",
+            "amended",
+            "--date=2000-01-01T00:00:00+00:00",
+        ],
+    );
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Amended: amended\n"));
+    assert!(stderr.contains("Author date: Sat Jan 1 00:00:00 2000 +0000\n"));
+    assert!(stderr.contains("Commit date: "));
+}
+
+#[test]
+fn commit_cleanup() {
+    let work_dir = common::init_repo_no_chdir();
+    let commit_message = |cleanup: &str| {
+        oaf(
+            &work_dir,
+            &[
+                "commit",
+                "--amend",
+                "-m",
+                "# Heading\n\nBody",
+                "--cleanup",
+                cleanup,
+            ],
+        );
+        git(&work_dir, &["log", "-1", "--format=%B"])
+    };
+    assert_eq!("# Heading\n\nBody", commit_message("verbatim"));
+    assert_eq!("Body", commit_message("strip"));
+}
+
+#[test]
+fn commit_large_file() {
+    let work_dir = common::init_repo_no_chdir();
+    fs::write(work_dir.path().join("large"), vec![0u8; 2048]).unwrap();
+    git(&work_dir, &["add", "large"]);
+    git(&work_dir, &["config", "oaf.max-file-size", "1k"]);
+    assert!(!commit_succeeds(&work_dir, &["-m", "large"]));
+    assert!(!commit_succeeds(&work_dir, &["-m", "large", "--no-all"]));
+    git(&work_dir, &["config", "oaf.max-file-size", "4k"]);
+    assert!(commit_succeeds(
+        &work_dir,
+        &["-m", "large", "--no-all", "--amend"]
+    ));
+    git(&work_dir, &["config", "oaf.max-file-size", "1k"]);
+    fs::write(work_dir.path().join("large"), vec![1u8; 2048]).unwrap();
+    assert!(!commit_succeeds(&work_dir, &["-m", "large"]));
+    assert!(commit_succeeds(
+        &work_dir,
+        &["-m", "large", "--allow-large"]
+    ));
+}
+
+#[test]
+fn sign_and_no_sign() {
+    let work_dir = common::init_repo_no_chdir();
+    let amend = |args: &[&str]| {
+        let mut amend_args = vec!["--amend", "-m", "amended"];
+        amend_args.extend(args);
+        commit_succeeds(&work_dir, &amend_args)
+    };
+    // Signing always fails, so success means signing was not attempted.
+    git(&work_dir, &["config", "gpg.program", "false"]);
+    assert!(amend(&[]));
+    assert!(!amend(&["--sign"]));
+    assert!(!amend(&["--sign=ABCDEF"]));
+    git(&work_dir, &["config", "commit.gpgsign", "true"]);
+    assert!(!amend(&[]));
+    assert!(amend(&["--no-sign"]));
+}
+
+/// Set a formatter, change foo.txt and commit it with --format.
+fn commit_format(work_dir: &tempfile::TempDir, formatter: &str) -> bool {
+    git(work_dir, &["config", "oaf.pre-commit-format", formatter]);
+    fs::write(work_dir.path().join("foo.txt"), "changed").unwrap();
+    commit_succeeds(work_dir, &["--format", "-m", "Format"])
+}
+
+#[test]
+fn commit_format_stages_changes() {
+    let work_dir = common::init_repo_no_chdir();
+    assert!(commit_format(&work_dir, "sed -i s/changed/formatted/"));
+    assert_eq!("formatted", git(&work_dir, &["show", "HEAD:foo.txt"]));
+}
+
+#[test]
+fn commit_format_aborts_if_staging_fails() {
+    let work_dir = common::init_repo_no_chdir();
+    // Holding the index lock makes the "git add" of the formatted files fail.
+    let formatter = "sed -i s/changed/formatted/ \"$@\"; touch .git/index.lock; :";
+    assert!(!commit_format(&work_dir, formatter));
+    assert_eq!("bar", git(&work_dir, &["show", "HEAD:foo.txt"]));
+}
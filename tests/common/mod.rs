@@ -1,10 +1,13 @@
 use std::env::set_current_dir;
 use std::fs::File;
 use std::io::Write;
+use std::ops::Deref;
+use std::path::Path;
 use std::process;
+use std::sync::{Mutex, MutexGuard};
 use tempfile::TempDir;
 
-use oaf::git::make_git_command;
+use oaf::git::{make_git_command, output_to_string};
 
 pub trait RunFallible {
     fn run_check(&mut self);
@@ -16,6 +19,42 @@ impl RunFallible for process::Command {
     }
 }
 
+/// The current directory is shared by every test in the process, so only one test may use it
+/// at a time.
+static CWD_LOCK: Mutex<()> = Mutex::new(());
+
+/// A repository that is the current directory until it is dropped.
+#[allow(dead_code)]
+pub struct CwdRepo {
+    work_dir: TempDir,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Deref for CwdRepo {
+    type Target = TempDir;
+    fn deref(&self) -> &TempDir {
+        &self.work_dir
+    }
+}
+
+impl AsRef<Path> for CwdRepo {
+    fn as_ref(&self) -> &Path {
+        self.work_dir.path()
+    }
+}
+
+/// Make work_dir the current directory, waiting for any other test that is using it.
+#[allow(dead_code)]
+pub fn chdir_to(work_dir: TempDir) -> CwdRepo {
+    // A failed test poisons the lock, but the current directory is still usable.
+    let lock = CWD_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    set_current_dir(&work_dir).expect("Failed to chdir to working directory");
+    CwdRepo {
+        work_dir,
+        _lock: lock,
+    }
+}
+
 #[allow(dead_code)]
 pub fn init_blank_repo() -> TempDir {
     let work_dir = TempDir::new().expect("Could not create temporary directory");
@@ -48,13 +87,63 @@ pub fn init_repo_no_chdir() -> TempDir {
     make_git_command(&["commit", "-am", "initial commit"])
         .current_dir(&work_dir)
         .run_check();
-    set_current_dir(&work_dir).expect("Failed to chdir to working directory");
     work_dir
 }
 
 #[allow(dead_code)]
-pub fn init_repo() -> TempDir {
-    let work_dir = init_repo_no_chdir();
-    set_current_dir(&work_dir).expect("Failed to chdir to working directory");
-    work_dir
+pub fn init_repo() -> CwdRepo {
+    chdir_to(init_repo_no_chdir())
+}
+
+/// Return a command that runs oaf in work_dir.
+#[allow(dead_code)]
+pub fn oaf_command(work_dir: &TempDir) -> process::Command {
+    let mut command = process::Command::new(env!("CARGO_BIN_EXE_oaf"));
+    command.current_dir(work_dir);
+    command
+}
+
+/// Run oaf in work_dir, whether or not it succeeds.
+#[allow(dead_code)]
+pub fn run_oaf(work_dir: &TempDir, args: &[&str]) -> process::Output {
+    oaf_command(work_dir).args(args).output().unwrap()
+}
+
+/// Run oaf in work_dir, asserting success, and return its output.
+#[allow(dead_code)]
+pub fn oaf(work_dir: &TempDir, args: &[&str]) -> String {
+    let output = run_oaf(work_dir, args);
+    assert!(output.status.success());
+    output_to_string(&output)
+}
+
+/// Run git in work_dir, asserting success, and return its output.
+#[allow(dead_code)]
+pub fn git(work_dir: &TempDir, args: &[&str]) -> String {
+    let output = make_git_command(args)
+        .current_dir(work_dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    output_to_string(&output)
+}
+
+/// Write a file in work_dir and commit it.
+#[allow(dead_code)]
+pub fn commit_file(work_dir: &TempDir, name: &str, contents: &str) {
+    std::fs::write(work_dir.path().join(name), contents).unwrap();
+    git(work_dir, &["add", name]);
+    git(
+        work_dir,
+        &["commit", "-q", "-m", &format!("change {}", name)],
+    );
+}
+
+#[allow(dead_code)]
+pub fn ref_exists(work_dir: &TempDir, reference: &str) -> bool {
+    make_git_command(&["show-ref", "--verify", "--quiet", reference])
+        .current_dir(work_dir)
+        .status()
+        .unwrap()
+        .success()
 }
@@ -0,0 +1,62 @@
+use std::fs;
+
+mod common;
+use common::{git, oaf, run_oaf};
+
+fn init_staged_and_unstaged() -> tempfile::TempDir {
+    let work_dir = common::init_repo_no_chdir();
+    fs::write(work_dir.path().join("staged.txt"), "changed").unwrap();
+    git(&work_dir, &["add", "staged.txt"]);
+    fs::write(work_dir.path().join("foo.txt"), "changed").unwrap();
+    work_dir
+}
+
+#[test]
+fn diff_before_first_commit() {
+    let work_dir = common::init_blank_repo();
+    fs::write(work_dir.path().join("foo.txt"), "bar\n").unwrap();
+    git(&work_dir, &["add", "foo.txt"]);
+    let output = run_oaf(&work_dir, &["diff"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("+++ b/foo.txt\n"));
+    assert!(stdout.contains("+bar\n"));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("No commits yet; showing all tracked content as additions."));
+}
+
+#[test]
+fn diff_default_shows_all_changes() {
+    let work_dir = init_staged_and_unstaged();
+    assert_eq!(
+        "foo.txt\nstaged.txt",
+        oaf(&work_dir, &["diff", "--name-only"])
+    );
+}
+
+#[test]
+fn diff_staged() {
+    let work_dir = init_staged_and_unstaged();
+    assert_eq!(
+        "staged.txt",
+        oaf(&work_dir, &["diff", "--name-only", "--staged"])
+    );
+}
+
+#[test]
+fn diff_unstaged() {
+    let work_dir = init_staged_and_unstaged();
+    assert_eq!(
+        "foo.txt",
+        oaf(&work_dir, &["diff", "--name-only", "--unstaged"])
+    );
+}
+
+#[test]
+fn diff_name_status() {
+    let work_dir = init_staged_and_unstaged();
+    assert_eq!(
+        "M\tfoo.txt\nA\tstaged.txt",
+        oaf(&work_dir, &["diff", "--name-status"])
+    );
+}
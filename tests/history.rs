@@ -0,0 +1,133 @@
+use std::fs;
+
+use oaf::worktree::EMPTY_TREE;
+mod common;
+use common::{commit_file, git, oaf, run_oaf};
+
+#[test]
+fn patch_reverse() {
+    let work_dir = common::init_repo_no_chdir();
+    commit_file(&work_dir, "first.txt", "contents");
+    commit_file(&work_dir, "second.txt", "contents");
+    let output = oaf(&work_dir, &["log", "--patch", "--reverse", "-n2"]);
+    let first = output.find("diff --git a/first.txt").unwrap();
+    let second = output.find("diff --git a/second.txt").unwrap();
+    assert!(first < second);
+    assert!(!output.contains("foo.txt"));
+}
+
+#[test]
+fn patch_with_stat() {
+    let work_dir = common::init_repo_no_chdir();
+    commit_file(&work_dir, "first.txt", "contents");
+    let output = oaf(&work_dir, &["log", "--patch-with-stat", "--reverse"]);
+    let stat = output.find(" first.txt | 1 +").unwrap();
+    let patch = output.find("diff --git a/first.txt").unwrap();
+    assert!(stat < patch);
+    assert!(output.find("diff --git a/foo.txt").unwrap() < stat);
+}
+
+#[test]
+fn log_summary() {
+    let work_dir = common::init_repo_no_chdir();
+    commit_file(&work_dir, "first.txt", "contents");
+    git(&work_dir, &["mv", "first.txt", "renamed.txt"]);
+    git(&work_dir, &["commit", "-m", "rename first.txt"]);
+    let output = oaf(&work_dir, &["log", "--oneline", "--summary", "-n2"]);
+    let rename = output
+        .find(" rename first.txt => renamed.txt (100%)")
+        .unwrap();
+    let create = output.find(" create mode 100644 first.txt").unwrap();
+    assert!(rename < create);
+}
+
+#[test]
+fn cherry_marks_picked_commits() {
+    let work_dir = common::init_repo_no_chdir();
+    git(&work_dir, &["switch", "-c", "feature"]);
+    commit_file(&work_dir, "picked.txt", "contents");
+    commit_file(&work_dir, "unpicked.txt", "contents");
+    git(&work_dir, &["switch", "main"]);
+    commit_file(&work_dir, "main.txt", "contents");
+    git(&work_dir, &["cherry-pick", "feature~1"]);
+    git(&work_dir, &["switch", "feature"]);
+    let marks: Vec<String> = oaf(&work_dir, &["cherry", "main"])
+        .lines()
+        .map(|line| {
+            let mut words = line.split(' ');
+            let mark = words.next().unwrap();
+            format!("{} {}", mark, words.skip(1).collect::<Vec<_>>().join(" "))
+        })
+        .collect();
+    assert_eq!(vec!["- change picked.txt", "+ change unpicked.txt"], marks);
+}
+
+#[test]
+fn blame_pattern_ranges() {
+    let work_dir = common::init_repo_no_chdir();
+    fs::write(
+        work_dir.path().join("code.c"),
+        "int first(void)\n{\n\treturn 1;\n}\n\nint second(void)\n{\n\treturn 2;\n}\n",
+    )
+    .unwrap();
+    git(&work_dir, &["add", "code.c"]);
+    git(&work_dir, &["commit", "-m", "Add code"]);
+    let blame_lines = |range: &str| -> Vec<String> {
+        oaf(&work_dir, &["blame", "-L", range, "code.c"])
+            .lines()
+            .map(|line| line.split_once(") ").unwrap().1.to_owned())
+            .collect()
+    };
+    assert_eq!(
+        vec!["int second(void)", "{", "\treturn 2;", "}"],
+        blame_lines(":second")
+    );
+    assert_eq!(vec!["{", "\treturn 1;", "}"], blame_lines("/^{/,/^}/"));
+}
+
+#[test]
+fn revno_find() {
+    let work_dir = common::init_repo_no_chdir();
+    git(
+        &work_dir,
+        &["commit", "-q", "--allow-empty", "-m", "second"],
+    );
+    assert_eq!(
+        git(&work_dir, &["rev-parse", "HEAD~1"]),
+        oaf(&work_dir, &["revno", "--find", "1"])
+    );
+    assert_eq!(EMPTY_TREE, oaf(&work_dir, &["revno", "--find", "0"]));
+    let second = oaf(&work_dir, &["revno", "--find", "2"]);
+    assert_eq!("2", oaf(&work_dir, &["revno", &second]));
+    assert!(!run_oaf(&work_dir, &["revno", "--find", "3"])
+        .status
+        .success());
+}
+
+#[test]
+fn revno_between() {
+    let work_dir = common::init_repo_no_chdir();
+    for message in ["second", "third"] {
+        git(&work_dir, &["commit", "-q", "--allow-empty", "-m", message]);
+    }
+    assert_eq!("2", oaf(&work_dir, &["revno", "HEAD~2", "HEAD"]));
+    assert_eq!("1", oaf(&work_dir, &["revno", "2", "3"]));
+    assert!(!run_oaf(&work_dir, &["revno", "HEAD", "HEAD~1"])
+        .status
+        .success());
+}
+
+#[test]
+fn revno_short_labels_branch() {
+    let work_dir = common::init_repo_no_chdir();
+    git(&work_dir, &["branch", "other"]);
+    git(&work_dir, &["tag", "v1"]);
+    git(
+        &work_dir,
+        &["commit", "-q", "--allow-empty", "-m", "second"],
+    );
+    assert_eq!("main@r2", oaf(&work_dir, &["revno", "--short"]));
+    assert_eq!("other@r1", oaf(&work_dir, &["revno", "--short", "other"]));
+    let sha = git(&work_dir, &["rev-parse", "--short=7", "v1"]);
+    assert_eq!(sha, oaf(&work_dir, &["revno", "--short", "v1"]));
+}
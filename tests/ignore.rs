@@ -0,0 +1,89 @@
+use std::fs;
+
+mod common;
+use common::{commit_file, git, oaf, oaf_command, run_oaf};
+
+#[test]
+fn skip_worktree() {
+    let work_dir = common::init_repo_no_chdir();
+    commit_file(&work_dir, "config.txt", "shared");
+    oaf(&work_dir, &["ignore-changes", "foo.txt"]);
+    oaf(
+        &work_dir,
+        &["ignore-changes", "--skip-worktree", "config.txt"],
+    );
+    assert_eq!(
+        "S config.txt\nh foo.txt",
+        git(&work_dir, &["ls-files", "-v"])
+    );
+    assert_eq!("config.txt\nfoo.txt", oaf(&work_dir, &["ignore-changes"]));
+    oaf(
+        &work_dir,
+        &["ignore-changes", "--unset", "--skip-worktree", "config.txt"],
+    );
+    assert_eq!("foo.txt", oaf(&work_dir, &["ignore-changes"]));
+}
+
+#[test]
+fn ignore_global() {
+    let work_dir = common::init_repo_no_chdir();
+    let home = tempfile::TempDir::new().unwrap();
+    let ignore_global = |pattern: &str| {
+        let status = oaf_command(&work_dir)
+            .args(["ignore", "--global", pattern])
+            .env("HOME", home.path())
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("GIT_CONFIG_GLOBAL")
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    ignore_global("*.swp");
+    let default_excludes = home.path().join(".config/git/ignore");
+    assert_eq!("*.swp\n", fs::read_to_string(default_excludes).unwrap());
+    assert!(!work_dir.path().join(".gitignore").exists());
+    git(
+        &home,
+        &[
+            "config",
+            "--file",
+            ".gitconfig",
+            "core.excludesFile",
+            "~/excludes",
+        ],
+    );
+    ignore_global("*~");
+    let configured = home.path().join("excludes");
+    assert_eq!("*~\n", fs::read_to_string(configured).unwrap());
+}
+
+#[test]
+fn unignore() {
+    let work_dir = common::init_repo_no_chdir();
+    let unignore_code = |files: &[&str]| {
+        let mut args = vec!["unignore"];
+        args.extend(files);
+        run_oaf(&work_dir, &args).status.code()
+    };
+    oaf(&work_dir, &["ignore", "build", "*.swp"]);
+    oaf(&work_dir, &["ignore", "--recurse", "target"]);
+    let gitignore = work_dir.path().join(".gitignore");
+    assert_eq!(Some(0), unignore_code(&["build", "target"]));
+    assert_eq!("/*.swp\n", fs::read_to_string(&gitignore).unwrap());
+    assert_eq!(Some(1), unignore_code(&["*.swp", "missing"]));
+    assert_eq!("", fs::read_to_string(&gitignore).unwrap());
+    assert_eq!(Some(1), unignore_code(&["missing"]));
+}
+
+#[test]
+fn ignore_tracked_file_warns() {
+    let work_dir = common::init_repo_no_chdir();
+    let output = run_oaf(&work_dir, &["ignore", "foo.txt"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("still tracked:\n  foo.txt\n"));
+    let status = run_oaf(&work_dir, &["status"]);
+    assert!(status.status.success());
+    assert!(!String::from_utf8_lossy(&status.stderr).contains("still tracked"));
+    let check = run_oaf(&work_dir, &["ignore", "--check"]);
+    assert_eq!(Some(1), check.status.code());
+}
@@ -0,0 +1,85 @@
+use git2::Repository;
+
+use oaf::branch::{check_links, find_pipeline_heads, rename_links, PipeNext, SiblingBranch};
+use oaf::git::{LocalBranchName, ReferenceSpec};
+mod common;
+use common::git;
+
+#[test]
+fn check_broken_link() {
+    let work_dir = common::init_repo_no_chdir();
+    for branch in ["foo", "bar"] {
+        git(&work_dir, &["branch", branch]);
+    }
+    let repo = Repository::open(&work_dir).unwrap();
+    let foo = PipeNext::from(LocalBranchName::from("foo".to_string()));
+    let bar = LocalBranchName::from("bar".to_string());
+    let (_, bar) = foo.insert_branch(&repo, bar).unwrap();
+    assert!(check_links(&repo).unwrap().is_empty());
+    bar.find_reference(&repo).unwrap().delete().unwrap();
+    assert_eq!(
+        vec!["bar is the next branch of foo, but is not linked back to it.".to_string()],
+        check_links(&repo).unwrap()
+    );
+}
+
+#[test]
+fn heads_of_two_pipelines() {
+    let work_dir = common::init_blank_repo();
+    let repo = Repository::open(work_dir).unwrap();
+    let foo = PipeNext::from(LocalBranchName::from("foo".to_string()));
+    let (_, bar) = foo
+        .insert_branch(&repo, LocalBranchName::from("bar".to_string()))
+        .unwrap();
+    bar.inverse()
+        .insert_branch(&repo, LocalBranchName::from("baz".to_string()))
+        .unwrap();
+    let qux = PipeNext::from(LocalBranchName::from("qux".to_string()));
+    qux.insert_branch(&repo, LocalBranchName::from("quux".to_string()))
+        .unwrap();
+    assert_eq!(
+        vec![
+            LocalBranchName::from("foo".to_string()),
+            LocalBranchName::from("qux".to_string()),
+        ],
+        find_pipeline_heads(&repo).unwrap()
+    );
+}
+
+#[test]
+fn rename_middle() {
+    let work_dir = common::init_blank_repo();
+    let repo = Repository::open(work_dir).unwrap();
+    let foo = PipeNext::from(LocalBranchName::from("foo".to_string()));
+    let bar = LocalBranchName::from("bar".to_string());
+    let (_, bar) = foo.insert_branch(&repo, bar).unwrap();
+    let baz = LocalBranchName::from("baz".to_string());
+    bar.inverse().insert_branch(&repo, baz).unwrap();
+    let old = LocalBranchName::from("bar".to_string());
+    let new = LocalBranchName::from("qux".to_string());
+    rename_links(&repo, &old, &new).unwrap();
+    let target = |reference: &str| {
+        repo.find_reference(reference)
+            .unwrap()
+            .symbolic_target()
+            .map(|s| s.to_owned())
+    };
+    assert_eq!(
+        Some("refs/heads/qux".to_string()),
+        target("refs/pipe-next/foo")
+    );
+    assert_eq!(
+        Some("refs/heads/qux".to_string()),
+        target("refs/pipe-prev/baz")
+    );
+    assert_eq!(
+        Some("refs/heads/baz".to_string()),
+        target("refs/pipe-next/qux")
+    );
+    assert_eq!(
+        Some("refs/heads/foo".to_string()),
+        target("refs/pipe-prev/qux")
+    );
+    assert!(PipeNext::from(old.clone()).find_reference(&repo).is_err());
+    assert!(PipeNext::from(old).inverse().find_reference(&repo).is_err());
+}
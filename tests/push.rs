@@ -0,0 +1,39 @@
+mod common;
+use common::{git, oaf, ref_exists, run_oaf};
+
+#[test]
+fn push_dry_run() {
+    let work_dir = common::init_repo_no_chdir();
+    let remote_dir = tempfile::TempDir::new().unwrap();
+    git(&remote_dir, &["init", "--bare", "-q"]);
+    let remote = remote_dir.path().to_string_lossy();
+    let push = |remote: &str| {
+        run_oaf(&work_dir, &["push", "--dry-run", remote])
+            .status
+            .success()
+    };
+    assert!(push(&remote));
+    assert!(!ref_exists(&remote_dir, "refs/heads/main"));
+    assert!(!push("/nonexistent/remote"));
+}
+
+#[test]
+fn push_pipeline() {
+    let work_dir = common::init_repo_no_chdir();
+    let remote_dir = tempfile::TempDir::new().unwrap();
+    git(&remote_dir, &["init", "--bare", "-q"]);
+    let remote = remote_dir.path().to_string_lossy();
+    oaf(&work_dir, &["switch-next", "-c", "feature"]);
+    assert_eq!(
+        "main: [new branch]\nfeature: [new branch]",
+        oaf(&work_dir, &["push", "--pipeline", &remote])
+    );
+    assert_eq!(
+        "refs/heads/feature\nrefs/heads/main",
+        git(&remote_dir, &["for-each-ref", "--format=%(refname)"])
+    );
+    assert_eq!(
+        "refs/heads/feature",
+        git(&work_dir, &["config", "branch.feature.merge"])
+    );
+}
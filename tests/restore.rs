@@ -0,0 +1,82 @@
+use std::fs;
+
+use oaf::git::make_git_command;
+mod common;
+use common::{commit_file, git, oaf, run_oaf};
+
+/// Commit conflicting changes to foo.txt on main and on "theirs".
+fn init_conflicting_branches() -> tempfile::TempDir {
+    let work_dir = common::init_repo_no_chdir();
+    git(&work_dir, &["switch", "-q", "-c", "theirs"]);
+    commit_file(&work_dir, "foo.txt", "theirs");
+    git(&work_dir, &["switch", "-q", "main"]);
+    commit_file(&work_dir, "foo.txt", "ours");
+    work_dir
+}
+
+fn git_merge_succeeds(work_dir: &tempfile::TempDir, branch: &str) -> bool {
+    make_git_command(&["merge", "-q", branch])
+        .current_dir(work_dir)
+        .output()
+        .unwrap()
+        .status
+        .success()
+}
+
+#[test]
+fn restore_staged() {
+    let work_dir = common::init_repo_no_chdir();
+    fs::write(work_dir.path().join("foo.txt"), "changed").unwrap();
+    git(&work_dir, &["add", "foo.txt"]);
+    oaf(&work_dir, &["restore", "--staged", "foo.txt"]);
+    assert_eq!("", git(&work_dir, &["diff", "--cached", "--name-only"]));
+    assert_eq!("foo.txt", git(&work_dir, &["diff", "--name-only"]));
+    assert_eq!(
+        "changed",
+        fs::read_to_string(work_dir.path().join("foo.txt")).unwrap()
+    );
+}
+
+#[test]
+fn restore_ours_and_theirs() {
+    let work_dir = init_conflicting_branches();
+    let restore = |side: &str| {
+        run_oaf(&work_dir, &["restore", side, "foo.txt"])
+            .status
+            .success()
+    };
+    assert!(!restore("--ours"));
+    assert!(!git_merge_succeeds(&work_dir, "theirs"));
+    let file = work_dir.path().join("foo.txt");
+    assert!(restore("--theirs"));
+    assert_eq!("theirs", fs::read_to_string(&file).unwrap());
+    assert!(restore("--ours"));
+    assert_eq!("ours", fs::read_to_string(&file).unwrap());
+}
+
+#[test]
+fn resolve_conflicts() {
+    let work_dir = init_conflicting_branches();
+    git(&work_dir, &["switch", "-q", "theirs"]);
+    commit_file(&work_dir, "bar.txt", "theirs");
+    git(&work_dir, &["switch", "-q", "main"]);
+    commit_file(&work_dir, "bar.txt", "ours");
+    assert!(!git_merge_succeeds(&work_dir, "theirs"));
+    assert_eq!(
+        "Remaining conflicts:\n  bar.txt",
+        oaf(&work_dir, &["resolve", "--theirs", "foo.txt"])
+    );
+    assert_eq!(
+        "theirs",
+        fs::read_to_string(work_dir.path().join("foo.txt")).unwrap()
+    );
+    assert_eq!("", oaf(&work_dir, &["resolve", "--ours", "--all"]));
+    assert_eq!(
+        "ours",
+        fs::read_to_string(work_dir.path().join("bar.txt")).unwrap()
+    );
+    assert_eq!(
+        "",
+        git(&work_dir, &["diff", "--name-only", "--diff-filter=U"])
+    );
+}
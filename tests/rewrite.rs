@@ -0,0 +1,69 @@
+use std::fs;
+
+mod common;
+use common::{commit_file, git, oaf, oaf_command, ref_exists, run_oaf};
+
+#[test]
+fn cherry_pick_conflict() {
+    let work_dir = common::init_repo_no_chdir();
+    git(&work_dir, &["switch", "-c", "feature"]);
+    commit_file(&work_dir, "foo.txt", "feature");
+    git(&work_dir, &["switch", "main"]);
+    commit_file(&work_dir, "foo.txt", "main");
+    let output = run_oaf(&work_dir, &["cherry-pick", "feature"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("foo.txt"));
+    assert!(stderr.contains("\"oaf commit\""));
+    assert!(!stderr.contains("hint:"));
+}
+
+#[test]
+fn revert_several_without_commit() {
+    let work_dir = common::init_repo_no_chdir();
+    commit_file(&work_dir, "first.txt", "contents");
+    commit_file(&work_dir, "second.txt", "contents");
+    oaf(&work_dir, &["revert", "--no-commit", "HEAD", "HEAD~1"]);
+    assert_eq!(
+        "change second.txt",
+        git(&work_dir, &["log", "-1", "--format=%s"])
+    );
+    assert!(!work_dir.path().join("first.txt").exists());
+    assert!(!work_dir.path().join("second.txt").exists());
+}
+
+#[test]
+fn revert_several() {
+    let work_dir = common::init_repo_no_chdir();
+    commit_file(&work_dir, "first.txt", "contents");
+    commit_file(&work_dir, "second.txt", "contents");
+    let status = oaf_command(&work_dir)
+        .args(["revert", "HEAD", "HEAD~1"])
+        .env("GIT_EDITOR", "true")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(
+        "Revert \"change first.txt\"",
+        git(&work_dir, &["log", "-1", "--format=%s"])
+    );
+    assert!(!work_dir.path().join("second.txt").exists());
+}
+
+#[test]
+fn rebase_abort_restores_wip() {
+    let work_dir = common::init_repo_no_chdir();
+    commit_file(&work_dir, "wip-file", "base");
+    git(&work_dir, &["switch", "-q", "-c", "feature"]);
+    commit_file(&work_dir, "conflict", "feature");
+    git(&work_dir, &["switch", "-q", "main"]);
+    commit_file(&work_dir, "conflict", "main");
+    git(&work_dir, &["switch", "-q", "feature"]);
+    let wip_file = work_dir.path().join("wip-file");
+    fs::write(&wip_file, "uncommitted").unwrap();
+    assert!(!run_oaf(&work_dir, &["rebase", "main"]).status.success());
+    assert_eq!("base", fs::read_to_string(&wip_file).unwrap());
+    assert!(run_oaf(&work_dir, &["rebase", "--abort"]).status.success());
+    assert_eq!("uncommitted", fs::read_to_string(&wip_file).unwrap());
+    assert!(!ref_exists(&work_dir, "refs/oaf-rebase-wip/feature"));
+}
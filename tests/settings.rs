@@ -0,0 +1,20 @@
+use std::env;
+
+use oaf::git::{get_setting, make_git_command, output_to_string, set_setting, SettingLocation};
+mod common;
+
+#[test]
+fn set_global_setting() {
+    let _work_dir = common::init_repo();
+    let home = tempfile::TempDir::new().unwrap();
+    let global_config = home.path().join("gitconfig");
+    env::set_var("GIT_CONFIG_GLOBAL", &global_config);
+    set_setting(SettingLocation::Local, "oaf.test-local", "local").unwrap();
+    set_setting(SettingLocation::Global, "oaf.test-global", "global").unwrap();
+    let global = make_git_command(&["config", "--global", "--list"])
+        .output()
+        .unwrap();
+    assert_eq!("oaf.test-global=global", output_to_string(&global));
+    assert_eq!(Some("local".to_string()), get_setting("oaf.test-local"));
+    assert_eq!(Some("global".to_string()), get_setting("oaf.test-global"));
+}
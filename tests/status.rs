@@ -0,0 +1,162 @@
+use std::fs::{self, create_dir, remove_file, File};
+use std::io::Write;
+
+use git2::Repository;
+use oaf::git::{make_git_command, LocalBranchName};
+use oaf::worktree::{BranchCommit, GitStatus, StatusEntry, WorktreeHead};
+mod common;
+use common::{git, oaf, run_oaf, RunFallible};
+
+fn write_file(name: &str, contents: &[u8]) {
+    let mut file = File::create(name).unwrap();
+    file.write_all(contents).expect("Failed to write file.");
+}
+
+fn commit_all(message: &str) {
+    make_git_command(&["add", "-A"]).run_check();
+    make_git_command(&["commit", "-qm", message]).run_check();
+}
+
+fn assert_same_status(repo: &Repository) {
+    let from_git = GitStatus::new().unwrap();
+    let from_repo = GitStatus::from_repo(repo).unwrap();
+    assert_eq!(from_git.head, from_repo.head);
+    assert_eq!(
+        from_git.iter().collect::<Vec<StatusEntry>>(),
+        from_repo.iter().collect::<Vec<StatusEntry>>()
+    );
+}
+
+#[test]
+fn no_commits() {
+    let work_dir = common::chdir_to(common::init_blank_repo());
+    let status = GitStatus::new().unwrap();
+    assert_eq!(
+        status.head,
+        WorktreeHead::Attached {
+            commit: BranchCommit::Initial,
+            head: LocalBranchName::from("main".to_string()),
+            upstream: None,
+        }
+    );
+    let repo = Repository::open(&work_dir).unwrap();
+    assert_eq!(status.head, GitStatus::from_repo(&repo).unwrap().head);
+}
+
+#[test]
+fn compare_with_git_status() {
+    let work_dir = common::init_repo();
+    let repo = Repository::open(&work_dir).unwrap();
+    for name in [
+        "conflict.txt",
+        "deleted.txt",
+        "modified.txt",
+        "moved.txt",
+        "removed.txt",
+    ] {
+        write_file(name, name.as_bytes());
+    }
+    commit_all("base");
+    make_git_command(&["branch", "other"]).run_check();
+    make_git_command(&["branch", "--set-upstream-to=other"]).run_check();
+    assert_same_status(&repo);
+    write_file("conflict.txt", b"main");
+    commit_all("main");
+    make_git_command(&["switch", "-q", "other"]).run_check();
+    write_file("conflict.txt", b"other");
+    commit_all("other");
+    make_git_command(&["switch", "-q", "main"]).run_check();
+    assert_same_status(&repo);
+    assert!(!make_git_command(&["merge", "-q", "other"])
+        .output()
+        .unwrap()
+        .status
+        .success());
+    remove_file("deleted.txt").unwrap();
+    write_file("modified.txt", b"changed");
+    make_git_command(&["mv", "moved.txt", "renamed.txt"]).run_check();
+    make_git_command(&["rm", "-q", "--cached", "removed.txt"]).run_check();
+    write_file("added.txt", b"added");
+    make_git_command(&["add", "added.txt"]).run_check();
+    write_file("untracked.txt", b"untracked");
+    create_dir("untracked-dir").unwrap();
+    write_file("untracked-dir/file.txt", b"untracked");
+    assert_same_status(&repo);
+    make_git_command(&["reset", "-q", "--hard"]).run_check();
+    make_git_command(&["switch", "-q", "--detach"]).run_check();
+    assert_same_status(&repo);
+    fs::rename("moved.txt", "unstaged.txt").unwrap();
+    assert_same_status(&repo);
+}
+
+#[test]
+fn status_exit_code() {
+    let work_dir = common::init_repo_no_chdir();
+    let status_code = |work_dir: &tempfile::TempDir, args: &[&str]| {
+        let mut status_args = vec!["status"];
+        status_args.extend(args);
+        run_oaf(work_dir, &status_args).status.code().unwrap()
+    };
+    assert_eq!(0, status_code(&work_dir, &[]));
+    assert_eq!(0, status_code(&work_dir, &["--exit-code"]));
+    fs::write(work_dir.path().join("new-file"), "content").unwrap();
+    assert_eq!(0, status_code(&work_dir, &[]));
+    assert_eq!(1, status_code(&work_dir, &["--exit-code"]));
+    let not_repo = tempfile::TempDir::new().unwrap();
+    assert_eq!(1, status_code(&not_repo, &[]));
+    assert_eq!(2, status_code(&not_repo, &["--exit-code"]));
+}
+
+#[test]
+fn status_detached() {
+    let work_dir = common::init_repo_no_chdir();
+    git(&work_dir, &["tag", "v1.2.0"]);
+    git(&work_dir, &["switch", "-q", "--detach"]);
+    assert_eq!(
+        "HEAD detached at v1.2.0",
+        oaf(&work_dir, &["status", "--branch-only"])
+    );
+    git(
+        &work_dir,
+        &["commit", "-q", "--allow-empty", "-m", "detached work"],
+    );
+    assert_eq!(
+        format!(
+            "HEAD detached at {}",
+            git(&work_dir, &["rev-parse", "--short", "HEAD"])
+        ),
+        oaf(&work_dir, &["status", "--branch-only"])
+    );
+}
+
+#[test]
+fn status_target() {
+    let work_dir = common::init_repo_no_chdir();
+    git(&work_dir, &["branch", "develop"]);
+    git(
+        &work_dir,
+        &["switch", "-q", "-c", "feature", "--track", "main"],
+    );
+    assert_eq!(
+        "On branch feature\nYour branch is up to date with 'main'.",
+        oaf(&work_dir, &["status"])
+    );
+    git(
+        &work_dir,
+        &[
+            "config",
+            "branch.feature.oaf-target-branch",
+            "refs/heads/develop",
+        ],
+    );
+    assert_eq!(
+        "On branch feature\nYour branch is up to date with 'main'.\n\
+        Tracking 'main', merging into 'develop'.",
+        oaf(&work_dir, &["status"])
+    );
+    git(&work_dir, &["branch", "--unset-upstream"]);
+    assert_eq!(
+        "On branch feature\nMerging into 'develop'.",
+        oaf(&work_dir, &["status"])
+    );
+}
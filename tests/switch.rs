@@ -1,10 +1,10 @@
-use std::fs::File;
+use std::fs::{self, read_to_string, File};
 use std::io::Write;
 
 use oaf::git::{get_current_branch, make_git_command, show_ref_match, BranchyName};
-use oaf::worktree::{stash_switch, SwitchErr, SwitchType};
+use oaf::worktree::{stash_switch, SwitchErr, SwitchOptions, SwitchType};
 mod common;
-use common::RunFallible;
+use common::{commit_file, git, oaf, ref_exists, run_oaf, RunFallible};
 
 #[test]
 fn non_existent() {
@@ -13,10 +13,264 @@ fn non_existent() {
     file.write_all(b"baz").expect("Failed to write file.");
     make_git_command(&["add", "bar.txt"]).run_check();
     let branchy_name = BranchyName::LocalBranch("foo".to_string().into());
-    if let Err(SwitchErr::NotFound) = stash_switch(SwitchType::WithStash(branchy_name)) {
+    if let Err(SwitchErr::NotFound) = stash_switch(
+        SwitchType::WithStash(branchy_name),
+        &SwitchOptions::default(),
+    ) {
     } else {
         panic!("Did not return NotFound");
     }
     assert!(get_current_branch().unwrap().branch_name() == "main");
     assert!(show_ref_match("refs/branch-wip/main").len() == 0);
 }
+
+#[test]
+fn switch_to_current_branch() {
+    let _work_dir = common::init_repo();
+    let mut file = File::create("foo.txt").unwrap();
+    file.write_all(b"changed").expect("Failed to write file.");
+    let branchy_name = BranchyName::LocalBranch("main".to_string().into());
+    stash_switch(
+        SwitchType::WithStash(branchy_name),
+        &SwitchOptions::default(),
+    )
+    .unwrap();
+    assert!(show_ref_match("refs/branch-wip/main").is_empty());
+    assert_eq!("changed", read_to_string("foo.txt").unwrap());
+}
+
+#[test]
+fn merge_in_progress() {
+    let _work_dir = common::init_repo();
+    make_git_command(&["branch", "foo"]).run_check();
+    let mut file = File::create(".git/MERGE_HEAD").unwrap();
+    file.write_all(b"0000000000000000000000000000000000000000\n")
+        .expect("Failed to write file.");
+    let branchy_name = BranchyName::LocalBranch("foo".to_string().into());
+    if let Err(SwitchErr::OperationInProgress("merge")) = stash_switch(
+        SwitchType::WithStash(branchy_name),
+        &SwitchOptions::default(),
+    ) {
+    } else {
+        panic!("Did not return OperationInProgress");
+    }
+    assert!(get_current_branch().unwrap().branch_name() == "main");
+}
+
+/// Leave foo.txt with unresolved conflicts, but without a merge in progress.
+fn make_unresolved_conflicts(work_dir: &tempfile::TempDir) {
+    git(work_dir, &["branch", "other"]);
+    git(work_dir, &["switch", "-q", "-c", "theirs"]);
+    commit_file(work_dir, "foo.txt", "theirs");
+    git(work_dir, &["switch", "-q", "main"]);
+    commit_file(work_dir, "foo.txt", "ours");
+    let merge = make_git_command(&["merge", "-q", "theirs"])
+        .current_dir(work_dir)
+        .output()
+        .unwrap();
+    assert!(!merge.status.success());
+    fs::remove_file(work_dir.path().join(".git/MERGE_HEAD")).unwrap();
+}
+
+#[test]
+fn switch_with_unresolved_conflicts() {
+    let work_dir = common::init_repo_no_chdir();
+    make_unresolved_conflicts(&work_dir);
+    for args in [&["switch", "other"][..], &["switch", "--keep", "other"]] {
+        let output = run_oaf(&work_dir, args);
+        assert_eq!(Some(1), output.status.code());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.starts_with("You have unresolved conflicts in these files:\n  foo.txt\n"));
+    }
+}
+
+#[test]
+fn switch_force_with_unresolved_conflicts() {
+    let work_dir = common::init_repo_no_chdir();
+    make_unresolved_conflicts(&work_dir);
+    oaf(&work_dir, &["switch", "--force", "other"]);
+    assert_eq!("other", git(&work_dir, &["branch", "--show-current"]));
+    oaf(&work_dir, &["switch", "main"]);
+    let contents = fs::read_to_string(work_dir.path().join("foo.txt")).unwrap();
+    assert!(contents.starts_with("<<<<<<< HEAD\nours\n"));
+    assert_eq!("M foo.txt", git(&work_dir, &["status", "--short"]));
+}
+
+#[test]
+fn create_from_full_ref() {
+    let work_dir = common::init_repo_no_chdir();
+    oaf(&work_dir, &["switch", "-c", "refs/heads/feature/foo"]);
+    let head = fs::read_to_string(work_dir.path().join(".git/HEAD")).unwrap();
+    assert_eq!(1, head.matches("refs/heads/").count());
+    assert_eq!("ref: refs/heads/feature/foo\n", head);
+}
+
+#[test]
+fn switch_create_from_remote_tracks() {
+    let work_dir = common::init_repo_no_chdir();
+    let base = git(&work_dir, &["rev-parse", "HEAD"]);
+    git(&work_dir, &["remote", "add", "origin", "."]);
+    git(
+        &work_dir,
+        &["update-ref", "refs/remotes/origin/main", &base],
+    );
+    git(&work_dir, &["commit", "--allow-empty", "-m", "local"]);
+    fs::write(work_dir.path().join("pending"), "wip").unwrap();
+    git(&work_dir, &["add", "pending"]);
+    oaf(
+        &work_dir,
+        &[
+            "switch",
+            "-c",
+            "feature",
+            "--from",
+            "origin/main",
+            "--track",
+        ],
+    );
+    assert_eq!(base, git(&work_dir, &["rev-parse", "HEAD"]));
+    assert_eq!("feature", git(&work_dir, &["branch", "--show-current"]));
+    assert_eq!(
+        "origin/main",
+        git(
+            &work_dir,
+            &["rev-parse", "--abbrev-ref", "feature@{upstream}"]
+        )
+    );
+    // The target is the branch being left, and its changes stay with it.
+    assert_eq!(
+        "refs/heads/main",
+        git(&work_dir, &["config", "branch.feature.oaf-target-branch"])
+    );
+    assert!(!work_dir.path().join("pending").exists());
+    assert!(ref_exists(&work_dir, "refs/branch-wip/main"));
+}
+
+/// Try to create a tracking branch at start_point with uncommitted changes, which must fail
+/// cleanly and leave the changes in place.
+fn assert_untrackable(work_dir: &tempfile::TempDir, start_point: &str) {
+    fs::write(work_dir.path().join("foo.txt"), "wip").unwrap();
+    let output = run_oaf(
+        work_dir,
+        &["switch", "-c", "feature", "--from", start_point, "--track"],
+    );
+    assert_eq!(Some(1), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be tracked"));
+    assert_eq!("main", git(work_dir, &["branch", "--show-current"]));
+    assert!(!ref_exists(work_dir, "refs/heads/feature"));
+    assert_eq!(
+        "wip",
+        fs::read_to_string(work_dir.path().join("foo.txt")).unwrap()
+    );
+    assert!(!ref_exists(work_dir, "refs/branch-wip/main"));
+}
+
+#[test]
+fn switch_create_from_tag_cannot_track() {
+    let work_dir = common::init_repo_no_chdir();
+    git(&work_dir, &["tag", "v1"]);
+    assert_untrackable(&work_dir, "v1");
+}
+
+#[test]
+fn switch_create_from_sha_cannot_track() {
+    let work_dir = common::init_repo_no_chdir();
+    let sha = git(&work_dir, &["rev-parse", "HEAD"]);
+    assert_untrackable(&work_dir, &sha);
+}
+
+#[test]
+fn switch_create_without_from_keeps_changes() {
+    let work_dir = common::init_repo_no_chdir();
+    fs::write(work_dir.path().join("pending"), "wip").unwrap();
+    git(&work_dir, &["add", "pending"]);
+    oaf(&work_dir, &["switch", "-c", "feature"]);
+    assert!(work_dir.path().join("pending").exists());
+}
+
+#[test]
+fn switch_from_requires_create() {
+    let work_dir = common::init_repo_no_chdir();
+    let output = run_oaf(&work_dir, &["switch", "feature", "--from", "main"]);
+    assert_eq!(Some(2), output.status.code());
+}
+
+#[test]
+fn switch_from_missing_start_point() {
+    let work_dir = common::init_repo_no_chdir();
+    let output = run_oaf(
+        &work_dir,
+        &["switch", "-c", "feature", "--from", "origin/nope"],
+    );
+    assert_eq!(Some(1), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("origin/nope not found"));
+    assert_eq!("main", git(&work_dir, &["branch", "--show-current"]));
+}
+
+#[test]
+fn switch_recent() {
+    let work_dir = common::init_repo_no_chdir();
+    for branch in ["first", "second"] {
+        git(&work_dir, &["branch", branch]);
+        oaf(&work_dir, &["-q", "switch", branch]);
+    }
+    oaf(&work_dir, &["-q", "switch", "main"]);
+    assert_eq!(
+        "1: second\n2: first",
+        oaf(&work_dir, &["switch", "--recent"])
+    );
+    oaf(&work_dir, &["-q", "switch", "--recent=2"]);
+    assert_eq!("first", git(&work_dir, &["branch", "--show-current"]));
+    assert_eq!(
+        "1: main\n2: second",
+        oaf(&work_dir, &["switch", "--recent"])
+    );
+}
+
+#[test]
+fn stash_list_and_apply() {
+    let work_dir = common::init_repo_no_chdir();
+    let file = work_dir.path().join("main-file");
+    fs::write(&file, "pending").unwrap();
+    git(&work_dir, &["add", "main-file"]);
+    oaf(&work_dir, &["switch", "-c", "other"]);
+    oaf(&work_dir, &["switch", "-k", "main"]);
+    oaf(&work_dir, &["switch", "other"]);
+    assert!(oaf(&work_dir, &["stash", "list"]).starts_with("main: "));
+    assert!(!file.exists());
+    assert!(!run_oaf(&work_dir, &["stash", "apply", "other"])
+        .status
+        .success());
+    oaf(&work_dir, &["stash", "apply", "main"]);
+    assert_eq!("pending", fs::read_to_string(&file).unwrap());
+    assert_eq!("", oaf(&work_dir, &["stash", "list"]));
+}
+
+#[test]
+fn switch_track_requires_create() {
+    let work_dir = common::init_repo_no_chdir();
+    git(&work_dir, &["branch", "feature"]);
+    for flag in ["--track", "--no-track"] {
+        let output = run_oaf(&work_dir, &["switch", "feature", flag]);
+        assert_eq!(Some(2), output.status.code());
+    }
+}
+
+#[test]
+fn switch_track_failure_restores_changes() {
+    let work_dir = common::init_repo_no_chdir();
+    // A remote-tracking branch without a configured remote cannot be tracked.
+    git(&work_dir, &["update-ref", "refs/remotes/gone/main", "HEAD"]);
+    fs::write(work_dir.path().join("foo.txt"), "wip").unwrap();
+    let output = run_oaf(
+        &work_dir,
+        &["switch", "-c", "feature", "--from", "gone/main", "--track"],
+    );
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!("main", git(&work_dir, &["branch", "--show-current"]));
+    assert_eq!(
+        "wip",
+        fs::read_to_string(work_dir.path().join("foo.txt")).unwrap()
+    );
+    assert!(!ref_exists(&work_dir, "refs/branch-wip/main"));
+}